@@ -3,7 +3,8 @@ mod pages;
 mod template;
 mod util;
 
-use crate::pages::Pages;
+use crate::markdown::parser::ParseOptions;
+use crate::pages::{Pages, RenderOptions};
 use crate::template::template::Template;
 use clap::Parser;
 use std::fs;
@@ -16,6 +17,44 @@ struct Args {
 
     #[clap(short, long)]
     out: Option<PathBuf>,
+
+    /// Turn bare URLs and email addresses in text into links
+    #[clap(long)]
+    bare_urls: bool,
+
+    /// Use curly quotes, en and em dashes and ellipses in text
+    #[clap(long)]
+    smart_punctuation: bool,
+
+    /// The language of every page on the site, such as `fr` for French punctuation spacing. This
+    /// only affects smart punctuation; the `lang` attribute of the HTML is set by the template.
+    #[clap(long, default_value = "en")]
+    lang: String,
+
+    /// Encode email links with HTML entities to hide them from scrapers
+    #[clap(long)]
+    obfuscate_emails: bool,
+
+    /// Show raw HTML containing scripts or event handlers as text instead of passing it through,
+    /// and point links and images with `javascript:` URLs at `#`
+    #[clap(long)]
+    no_scripts: bool,
+
+    /// Add a `#` link to each heading, for linking to sections of a page
+    #[clap(long)]
+    heading_anchors: bool,
+
+    /// Render emoji with the `emoji.html` template, for example to give them an `aria-label`
+    #[clap(long)]
+    emoji_template: bool,
+
+    /// The lowest heading level included in the `{toc}` table of contents
+    #[clap(long, default_value_t = 1)]
+    toc_min_level: u32,
+
+    /// The highest heading level included in the `{toc}` table of contents
+    #[clap(long, default_value_t = 6)]
+    toc_max_level: u32,
 }
 
 fn main() {
@@ -30,9 +69,23 @@ fn main() {
     }
 
     let template = Template::load("test", &args.path.join("template"));
-    let pages = Pages::load(&args.path.join("pages"));
+    let parse_options = ParseOptions {
+        bare_urls: args.bare_urls,
+        smart_punctuation: args.smart_punctuation,
+        language: args.lang,
+    };
+    let render_options = RenderOptions {
+        obfuscate_emails: args.obfuscate_emails,
+        no_scripts: args.no_scripts,
+        heading_anchors: args.heading_anchors,
+        emoji_template: args.emoji_template,
+        toc_min_level: args.toc_min_level,
+        toc_max_level: args.toc_max_level,
+    };
+
+    let pages = Pages::load(&args.path.join("pages"), &parse_options);
 
-    let rendered_pages = pages.render(&template);
+    let rendered_pages = pages.render(&template, &render_options);
     for (page_name, rendered_page) in rendered_pages {
         fs::write(out_path.join(format!("{}.html", page_name)), rendered_page).unwrap();
     }
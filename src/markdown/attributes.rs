@@ -0,0 +1,136 @@
+use crate::util::html::Attributes;
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+/// A single attribute: `#id`, `.class` or `key=value`, where the value may be quoted
+const ATTRIBUTE_PATTERN: &str =
+    r#"#([\w-]+)|\.([\w-]+)|([\w-]+)=(?:"([^"]*)"|'([^']*)'|([^\s"'{}]+))"#;
+
+/// Parses an attribute block (`{#id .class key=value}`) at the start of the text, returning the
+/// attributes along with the number of bytes consumed
+pub fn parse_attributes(text: &str) -> Option<(Attributes, usize)> {
+    lazy_static! {
+        static ref ATTRIBUTE_BLOCK_PATTERN: Regex = Regex::new(&format!(
+            r"^\{{[ \t]*((?:{0})(?:[ \t]+(?:{0}))*)[ \t]*\}}",
+            ATTRIBUTE_PATTERN
+        ))
+        .unwrap();
+        static ref SINGLE_ATTRIBUTE_PATTERN: Regex = Regex::new(ATTRIBUTE_PATTERN).unwrap();
+    }
+
+    let block = ATTRIBUTE_BLOCK_PATTERN.captures(text).ok()??;
+
+    let mut attributes = Attributes::default();
+    for caps in SINGLE_ATTRIBUTE_PATTERN.captures_iter(block.get(1).unwrap().as_str()) {
+        let caps = caps.ok()?;
+
+        if let Some(id) = caps.get(1) {
+            attributes.id = Some(id.as_str().to_string());
+        } else if let Some(class) = caps.get(2) {
+            attributes.classes.push(class.as_str().to_string());
+        } else {
+            let name = caps.get(3).unwrap().as_str();
+            let value = caps
+                .get(4)
+                .or(caps.get(5))
+                .or(caps.get(6))
+                .unwrap()
+                .as_str();
+
+            match name {
+                "id" => attributes.id = Some(value.to_string()),
+                "class" => attributes
+                    .classes
+                    .extend(value.split_whitespace().map(str::to_string)),
+                _ => attributes.pairs.push((name.to_string(), value.to_string())),
+            }
+        }
+    }
+
+    Some((attributes, block.get(0).unwrap().end()))
+}
+
+/// Splits an attribute block off of the end of the text, returning the text before it without
+/// trailing whitespace
+pub fn strip_trailing_attributes(text: &str) -> (&str, Option<Attributes>) {
+    let trimmed = text.trim_end();
+
+    if trimmed.ends_with('}') {
+        for (index, _) in trimmed.match_indices('{').rev() {
+            match parse_attributes(&trimmed[index..]) {
+                Some((attributes, length)) if index + length == trimmed.len() => {
+                    return (trimmed[..index].trim_end(), Some(attributes));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    (text, None)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::attributes::{parse_attributes, strip_trailing_attributes};
+    use crate::util::html::Attributes;
+
+    #[test]
+    fn test_parse_attributes() {
+        assert_eq!(
+            parse_attributes("{#intro .wide .dark data-x=1 title=\"A title\" class='a b'} rest"),
+            Some((
+                Attributes {
+                    id: Some("intro".to_string()),
+                    classes: vec![
+                        "wide".to_string(),
+                        "dark".to_string(),
+                        "a".to_string(),
+                        "b".to_string()
+                    ],
+                    pairs: vec![
+                        ("data-x".to_string(), "1".to_string()),
+                        ("title".to_string(), "A title".to_string()),
+                    ],
+                },
+                57
+            ))
+        );
+        assert_eq!(
+            parse_attributes("{ #a }"),
+            Some((
+                Attributes {
+                    id: Some("a".to_string()),
+                    ..Attributes::default()
+                },
+                6
+            ))
+        );
+
+        assert_eq!(parse_attributes("{}"), None);
+        assert_eq!(parse_attributes("{toc}"), None);
+        assert_eq!(parse_attributes("{#a b}"), None);
+        assert_eq!(parse_attributes(" {#a}"), None);
+    }
+
+    #[test]
+    fn test_strip_trailing_attributes() {
+        let class = |class: &str| Attributes {
+            classes: vec![class.to_string()],
+            ..Attributes::default()
+        };
+
+        assert_eq!(
+            strip_trailing_attributes("A header {.big}  "),
+            ("A header", Some(class("big")))
+        );
+        assert_eq!(
+            strip_trailing_attributes("{x} and {.y}"),
+            ("{x} and", Some(class("y")))
+        );
+        assert_eq!(
+            strip_trailing_attributes("Not {.attributes} here"),
+            ("Not {.attributes} here", None)
+        );
+        assert_eq!(strip_trailing_attributes("{x}"), ("{x}", None));
+    }
+}
@@ -1,4 +1,6 @@
-use crate::markdown::document::{Element, Line};
+use crate::markdown::attributes::strip_trailing_attributes;
+use crate::markdown::document::{Element, InlineElement, Line, ListItem};
+use crate::util::html::Attributes;
 use std::mem;
 
 #[derive(Debug, Default)]
@@ -19,17 +21,162 @@ impl ParagraphBuilder {
         self.add_line(Line::from_str(s));
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Takes the lines without finishing them as a paragraph, for when they turn out to be the
+    /// terms of a definition list
+    pub fn take_lines(&mut self) -> Vec<Line> {
+        mem::take(&mut self.lines)
+            .iter()
+            .map(|line| Line::from_str_at(line.to_plain_text().trim(), line.source_line))
+            .collect()
+    }
+
     pub fn finish(&mut self, out_vec: &mut Vec<Element>) {
         if self.lines.len() > 0 {
-            out_vec.push(Element::Paragraph(mem::replace(&mut self.lines, vec![])));
+            let mut lines = mem::replace(&mut self.lines, vec![]);
+
+            // Attributes on a line of their own at the end apply to the whole paragraph
+            let mut attributes = Attributes::default();
+            if lines.len() > 1 {
+                if let ("", Some(parsed)) =
+                    strip_trailing_attributes(lines.last().unwrap().to_plain_text().trim())
+                {
+                    attributes = parsed;
+                    lines.pop();
+                }
+            }
+
+            // Line breaks are found by the inline parser, which keeps the trailing spaces of the
+            // other lines. A hard break at the end of the paragraph is ignored.
+            let last = lines.last_mut().unwrap();
+            *last = Line::from_str_at(last.to_plain_text().trim_end(), last.source_line);
+
+            out_vec.push(Element::Paragraph(lines, attributes));
+        }
+    }
+
+    /// Finishes the paragraph as a header instead, for when it is followed by a setext underline
+    pub fn finish_as_header(&mut self, level: u32, out_vec: &mut Vec<Element>) {
+        let mut attributes = Attributes::default();
+
+        let lines = mem::take(&mut self.lines);
+        let mut header_line = Line {
+            source_line: lines[0].source_line,
+            ..Line::default()
+        };
+        let last_index = lines.len() - 1;
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                header_line
+                    .elements
+                    .push(InlineElement::Text("\n".to_string()));
+            }
+
+            let text = line.to_plain_text();
+            let mut text = text.trim_end();
+            if index == last_index {
+                if let (rest, Some(parsed)) = strip_trailing_attributes(text) {
+                    text = rest;
+                    attributes = parsed;
+                }
+            }
+
+            header_line
+                .elements
+                .push(InlineElement::Text(text.to_string()));
+        }
+
+        out_vec.push(Element::Header {
+            level,
+            id: attributes.id.take().unwrap_or_default(),
+            line: header_line,
+            attributes,
+        });
+    }
+}
+
+/// Collects the raw lines belonging to a container block (such as a list item) so that they can be
+/// parsed as blocks of their own once the container is closed.
+#[derive(Debug, Default)]
+pub struct ContainerBuilder {
+    lines: Vec<String>,
+}
+
+impl ContainerBuilder {
+    pub fn add_line(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    /// Returns true if no non-blank lines have been added
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|line| line.trim().is_empty())
+    }
+
+    pub fn last_line_is_blank(&self) -> bool {
+        self.lines.last().is_none_or(|line| line.trim().is_empty())
+    }
+
+    /// Takes the collected lines, leaving out any trailing blank lines
+    pub fn finish(&mut self) -> Vec<String> {
+        while self.last_line_is_blank() && !self.lines.is_empty() {
+            self.lines.pop();
+        }
+
+        mem::take(&mut self.lines)
+    }
+}
+
+#[derive(Debug)]
+pub struct ListBuilder {
+    ordered: bool,
+    start: u32,
+    tight: bool,
+    items: Vec<ListItem>,
+}
+
+impl ListBuilder {
+    pub fn new(ordered: bool, start: u32) -> Self {
+        ListBuilder {
+            ordered,
+            start,
+            tight: true,
+            items: vec![],
+        }
+    }
+
+    /// Adds a parsed item. `loose` marks that the item's blocks are separated by blank lines, which
+    /// makes the entire list loose.
+    pub fn add_item(&mut self, item: ListItem, loose: bool) {
+        self.items.push(item);
+
+        if loose {
+            self.tight = false;
+        }
+    }
+
+    /// Marks that two items are separated by a blank line
+    pub fn set_loose(&mut self) {
+        self.tight = false;
+    }
+
+    pub fn finish(self) -> Element {
+        Element::List {
+            ordered: self.ordered,
+            start: self.start,
+            tight: self.tight,
+            items: self.items,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::markdown::builder::ParagraphBuilder;
-    use crate::markdown::document::{Element, Line};
+    use crate::markdown::builder::{ContainerBuilder, ListBuilder, ParagraphBuilder};
+    use crate::markdown::document::{Element, InlineElement, Line, ListItem};
+    use crate::util::html::Attributes;
 
     #[test]
     fn test_paragraph_builder() {
@@ -40,7 +187,10 @@ mod test {
         builder.finish(&mut out_vec);
         assert_eq!(
             out_vec,
-            vec![Element::Paragraph(vec![Line::from_str("A test line")])]
+            vec![Element::Paragraph(
+                vec![Line::from_str("A test line")],
+                Attributes::default()
+            )]
         );
         out_vec.clear();
 
@@ -50,10 +200,10 @@ mod test {
         builder.finish(&mut out_vec);
         assert_eq!(
             out_vec,
-            vec![Element::Paragraph(vec![
-                Line::from_str("line one"),
-                Line::from_str("second line")
-            ])]
+            vec![Element::Paragraph(
+                vec![Line::from_str("line one"), Line::from_str("second line")],
+                Attributes::default()
+            )]
         );
         out_vec.clear();
 
@@ -65,10 +215,10 @@ mod test {
         builder.finish(&mut out_vec);
         assert_eq!(
             out_vec,
-            vec![Element::Paragraph(vec![
-                Line::from_str("line one"),
-                Line::from_str("second line")
-            ])]
+            vec![Element::Paragraph(
+                vec![Line::from_str("line one"), Line::from_str("second line")],
+                Attributes::default()
+            )]
         );
         out_vec.clear();
 
@@ -78,11 +228,130 @@ mod test {
         builder.finish(&mut out_vec);
         assert_eq!(
             out_vec,
-            vec![Element::Paragraph(vec![
-                Line::from_str("line one"),
-                Line::from_str("second line")
-            ])]
+            vec![Element::Paragraph(
+                vec![Line::from_str("line one"), Line::from_str("second line")],
+                Attributes::default()
+            )]
+        );
+        out_vec.clear();
+    }
+
+    #[test]
+    fn test_paragraph_builder_finish_as_header() {
+        let mut out_vec = vec![];
+
+        let mut builder = ParagraphBuilder::default();
+        builder.add_str("A header");
+        builder.finish_as_header(1, &mut out_vec);
+        assert_eq!(
+            out_vec,
+            vec![Element::Header {
+                level: 1,
+                id: String::new(),
+                line: Line::from_str("A header"),
+                attributes: Attributes::default(),
+            }]
         );
+        assert!(builder.is_empty());
         out_vec.clear();
+
+        let mut builder = ParagraphBuilder::default();
+        builder.add_str("A header");
+        builder.add_str("over two lines");
+        builder.finish_as_header(2, &mut out_vec);
+        assert_eq!(
+            out_vec,
+            vec![Element::Header {
+                level: 2,
+                id: String::new(),
+                line: Line::new(vec![
+                    InlineElement::Text("A header".to_string()),
+                    InlineElement::Text("\n".to_string()),
+                    InlineElement::Text("over two lines".to_string()),
+                ]),
+                attributes: Attributes::default(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_container_builder() {
+        let mut builder = ContainerBuilder::default();
+        assert!(builder.is_empty());
+        assert!(builder.last_line_is_blank());
+
+        builder.add_line("".to_string());
+        assert!(builder.is_empty());
+
+        builder.add_line("first line".to_string());
+        assert!(!builder.is_empty());
+        assert!(!builder.last_line_is_blank());
+
+        builder.add_line("  ".to_string());
+        builder.add_line("second line".to_string());
+        builder.add_line("".to_string());
+        assert!(builder.last_line_is_blank());
+
+        assert_eq!(
+            builder.finish(),
+            vec![
+                "".to_string(),
+                "first line".to_string(),
+                "  ".to_string(),
+                "second line".to_string()
+            ]
+        );
+        assert!(builder.is_empty());
+        assert_eq!(builder.finish(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_list_builder() {
+        let item = || {
+            ListItem::Plain(vec![Element::Paragraph(
+                vec![Line::from_str("item")],
+                Attributes::default(),
+            )])
+        };
+
+        let mut builder = ListBuilder::new(false, 1);
+        builder.add_item(item(), false);
+        builder.add_item(item(), false);
+        assert_eq!(
+            builder.finish(),
+            Element::List {
+                ordered: false,
+                start: 1,
+                tight: true,
+                items: vec![item(), item()]
+            }
+        );
+
+        let mut builder = ListBuilder::new(true, 3);
+        builder.add_item(item(), false);
+        builder.add_item(item(), true);
+        assert_eq!(
+            builder.finish(),
+            Element::List {
+                ordered: true,
+                start: 3,
+                tight: false,
+                items: vec![item(), item()]
+            }
+        );
+
+        let mut builder = ListBuilder::new(true, 1);
+        builder.add_item(item(), false);
+        builder.set_loose();
+        builder.add_item(item(), false);
+        assert_eq!(
+            builder.finish(),
+            Element::List {
+                ordered: true,
+                start: 1,
+                tight: false,
+                items: vec![item(), item()]
+            }
+        );
     }
 }
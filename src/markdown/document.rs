@@ -1,36 +1,463 @@
+use crate::util::html::Attributes;
 use std::fmt;
 use std::fmt::Formatter;
 
 #[derive(Debug, Default, Eq, PartialEq)]
 pub struct Document {
     pub elements: Vec<Element>,
+    /// Footnotes in order of their first reference
+    pub footnotes: Vec<Footnote>,
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq)]
+pub struct Footnote {
+    pub label: String,
+    pub elements: Vec<Element>,
+    /// The number of times the footnote is referenced
+    pub references: u32,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Line {
     pub elements: Vec<InlineElement>,
+    /// The line of the Markdown text that this comes from, starting at 1, or 0 if it isn't known
+    pub source_line: usize,
+}
+
+/// Lines are equal if they have the same content, wherever they come from
+impl PartialEq for Line {
+    fn eq(&self, other: &Self) -> bool {
+        self.elements == other.elements
+    }
 }
 
+impl Eq for Line {}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Element {
-    Header(u32, Line),
-    Paragraph(Vec<Line>),
+    /// `id` is made from the header's text and is unique within the document, which is assigned
+    /// once the document has been parsed
+    Header {
+        level: u32,
+        id: String,
+        line: Line,
+        /// Attributes other than the id, which is moved into `id`
+        attributes: Attributes,
+    },
+    /// Holds a line for each line of the text while blocks are parsed, which are joined into one
+    /// line with breaks between them once their inline content is parsed
+    Paragraph(Vec<Line>, Attributes),
+    ThematicBreak,
+    CodeBlock {
+        info: String,
+        lines: Vec<String>,
+        attributes: Attributes,
+    },
+    List {
+        ordered: bool,
+        start: u32,
+        tight: bool,
+        items: Vec<ListItem>,
+    },
+    BlockQuote(Vec<Element>),
+    /// A callout such as a note or a warning, written as a block quote starting with `[!NOTE]` or
+    /// as a `:::note` fenced container. `kind` is lowercase, and `title` defaults to the kind.
+    Admonition {
+        kind: String,
+        title: Line,
+        body: Vec<Element>,
+    },
+    Table {
+        alignments: Vec<Alignment>,
+        header: Vec<Line>,
+        rows: Vec<Vec<Line>>,
+    },
+    /// Terms (`Term`) followed by their definitions (`: definition`)
+    DefinitionList {
+        tight: bool,
+        items: Vec<DefinitionItem>,
+    },
+    /// Display math in TeX, written between `$$` on lines of its own or in a `math` fence.
+    /// `source_line` is the line of the Markdown text that the math starts on.
+    MathBlock {
+        source: String,
+        source_line: usize,
+    },
+    /// Raw HTML, which is passed through without being parsed
+    HtmlBlock(Vec<String>),
+    /// A footnote definition (`[^label]: text`), which is moved into `Document::footnotes` once
+    /// the document has been parsed
+    FootnoteDefinition {
+        label: String,
+        elements: Vec<Element>,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ListItem {
+    Plain(Vec<Element>),
+    /// A GFM task list item, written as `- [ ]` or `- [x]`
+    Task {
+        checked: bool,
+        elements: Vec<Element>,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct DefinitionItem {
+    pub terms: Vec<Line>,
+    /// Each definition may contain several blocks, such as multiple paragraphs
+    pub definitions: Vec<Vec<Element>>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum InlineElement {
     Text(String),
+    Code(String),
     Emphasis(u32, Line),
+    Strikethrough(Line),
+    Highlight(Line),
+    Superscript(Line),
+    Subscript(Line),
+    /// Raw HTML, such as a tag or a comment
+    Html(String),
+    /// A line break within a paragraph
+    SoftBreak,
+    /// A hard line break, written as two or more spaces or a backslash at the end of a line
+    HardBreak,
+    /// Math in TeX, written as `$x$` or as `$$x$$` for display math
+    Math {
+        source: String,
+        display: bool,
+    },
+    /// An emoji written as a shortcode, such as `:smile:`
+    Emoji {
+        shortcode: String,
+        emoji: String,
+    },
+    Link {
+        text: Line,
+        url: String,
+        title: Option<String>,
+        attributes: Attributes,
+    },
+    Image {
+        alt: Line,
+        url: String,
+        title: Option<String>,
+        attributes: Attributes,
+    },
+    /// A reference to a footnote (`[^label]`). `number` is the footnote's position in
+    /// `Document::footnotes` and `occurrence` counts the references to the same footnote, both
+    /// starting at 1 once the footnotes have been resolved.
+    FootnoteReference {
+        label: String,
+        number: u32,
+        occurrence: u32,
+    },
+}
+
+impl Element {
+    /// Calls `f` on every line of inline content in the element, including nested blocks
+    pub fn for_each_line_mut(&mut self, f: &mut dyn FnMut(&mut Line)) {
+        match self {
+            Element::Header { line, .. } => f(line),
+            Element::Paragraph(lines, _) => lines.iter_mut().for_each(f),
+            Element::ThematicBreak
+            | Element::CodeBlock { .. }
+            | Element::MathBlock { .. }
+            | Element::HtmlBlock(_) => {}
+            Element::List { items, .. } => {
+                for element in items.iter_mut().flat_map(ListItem::elements_mut) {
+                    element.for_each_line_mut(f);
+                }
+            }
+            Element::BlockQuote(elements) | Element::FootnoteDefinition { elements, .. } => {
+                for element in elements {
+                    element.for_each_line_mut(f);
+                }
+            }
+            Element::Admonition { title, body, .. } => {
+                f(title);
+                for element in body {
+                    element.for_each_line_mut(f);
+                }
+            }
+            Element::Table { header, rows, .. } => {
+                header
+                    .iter_mut()
+                    .chain(rows.iter_mut().flatten())
+                    .for_each(f);
+            }
+            Element::DefinitionList { items, .. } => {
+                for item in items {
+                    for line in &mut item.terms {
+                        f(line);
+                    }
+
+                    for element in item.definitions.iter_mut().flatten() {
+                        element.for_each_line_mut(f);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calls `f` on the element, followed by the blocks nested inside of it
+    pub fn for_each_block_mut(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        f(self);
+
+        match self {
+            Element::List { items, .. } => {
+                for element in items.iter_mut().flat_map(ListItem::elements_mut) {
+                    element.for_each_block_mut(f);
+                }
+            }
+            Element::BlockQuote(elements)
+            | Element::Admonition { body: elements, .. }
+            | Element::FootnoteDefinition { elements, .. } => {
+                for element in elements {
+                    element.for_each_block_mut(f);
+                }
+            }
+            Element::DefinitionList { items, .. } => {
+                for element in items
+                    .iter_mut()
+                    .flat_map(|item| &mut item.definitions)
+                    .flatten()
+                {
+                    element.for_each_block_mut(f);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Calls `f` on every line of inline content in the element, including nested blocks
+    pub fn for_each_line(&self, f: &mut dyn FnMut(&Line)) {
+        match self {
+            Element::Header { line, .. } => f(line),
+            Element::Paragraph(lines, _) => lines.iter().for_each(f),
+            Element::ThematicBreak
+            | Element::CodeBlock { .. }
+            | Element::MathBlock { .. }
+            | Element::HtmlBlock(_) => {}
+            Element::List { items, .. } => {
+                for element in items.iter().flat_map(ListItem::elements) {
+                    element.for_each_line(f);
+                }
+            }
+            Element::BlockQuote(elements) | Element::FootnoteDefinition { elements, .. } => {
+                for element in elements {
+                    element.for_each_line(f);
+                }
+            }
+            Element::Admonition { title, body, .. } => {
+                f(title);
+                for element in body {
+                    element.for_each_line(f);
+                }
+            }
+            Element::Table { header, rows, .. } => {
+                header.iter().chain(rows.iter().flatten()).for_each(f);
+            }
+            Element::DefinitionList { items, .. } => {
+                for item in items {
+                    item.terms.iter().for_each(&mut *f);
+
+                    for element in item.definitions.iter().flatten() {
+                        element.for_each_line(f);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calls `f` on the element, followed by the blocks nested inside of it
+    pub fn for_each_block(&self, f: &mut dyn FnMut(&Element)) {
+        f(self);
+
+        match self {
+            Element::List { items, .. } => {
+                for element in items.iter().flat_map(ListItem::elements) {
+                    element.for_each_block(f);
+                }
+            }
+            Element::BlockQuote(elements)
+            | Element::Admonition { body: elements, .. }
+            | Element::FootnoteDefinition { elements, .. } => {
+                for element in elements {
+                    element.for_each_block(f);
+                }
+            }
+            Element::DefinitionList { items, .. } => {
+                for element in items.iter().flat_map(|item| &item.definitions).flatten() {
+                    element.for_each_block(f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ListItem {
+    pub fn elements(&self) -> &Vec<Element> {
+        match self {
+            ListItem::Plain(elements) | ListItem::Task { elements, .. } => elements,
+        }
+    }
+
+    pub fn elements_mut(&mut self) -> &mut Vec<Element> {
+        match self {
+            ListItem::Plain(elements) | ListItem::Task { elements, .. } => elements,
+        }
+    }
 }
 
 impl Line {
+    pub fn new(elements: Vec<InlineElement>) -> Self {
+        Line {
+            elements,
+            source_line: 0,
+        }
+    }
+
     pub fn from_str(s: &str) -> Self {
+        Line::new(vec![InlineElement::Text(s.to_string())])
+    }
+
+    /// Creates a line of text that comes from the given line of the Markdown text
+    pub fn from_str_at(s: &str, source_line: usize) -> Self {
         Line {
-            elements: vec![InlineElement::Text(s.to_string())],
+            source_line,
+            ..Line::from_str(s)
+        }
+    }
+
+    pub fn to_plain_text(&self) -> String {
+        self.elements
+            .iter()
+            .map(|element| element.to_plain_text())
+            .collect()
+    }
+
+    /// Calls `f` on every inline element, followed by the elements nested inside of it
+    pub fn for_each_element_mut(&mut self, f: &mut dyn FnMut(&mut InlineElement)) {
+        for element in &mut self.elements {
+            f(element);
+
+            match element {
+                InlineElement::Emphasis(_, line)
+                | InlineElement::Strikethrough(line)
+                | InlineElement::Highlight(line)
+                | InlineElement::Superscript(line)
+                | InlineElement::Subscript(line)
+                | InlineElement::Link { text: line, .. }
+                | InlineElement::Image { alt: line, .. } => line.for_each_element_mut(f),
+                InlineElement::Text(_)
+                | InlineElement::Code(_)
+                | InlineElement::Html(_)
+                | InlineElement::SoftBreak
+                | InlineElement::HardBreak
+                | InlineElement::Math { .. }
+                | InlineElement::Emoji { .. }
+                | InlineElement::FootnoteReference { .. } => {}
+            }
+        }
+    }
+
+    /// Calls `f` on every inline element, followed by the elements nested inside of it
+    pub fn for_each_element(&self, f: &mut dyn FnMut(&InlineElement)) {
+        for element in &self.elements {
+            f(element);
+
+            match element {
+                InlineElement::Emphasis(_, line)
+                | InlineElement::Strikethrough(line)
+                | InlineElement::Highlight(line)
+                | InlineElement::Superscript(line)
+                | InlineElement::Subscript(line)
+                | InlineElement::Link { text: line, .. }
+                | InlineElement::Image { alt: line, .. } => line.for_each_element(f),
+                _ => {}
+            }
         }
     }
 }
 
+impl InlineElement {
+    pub fn to_plain_text(&self) -> String {
+        match self {
+            InlineElement::Text(text)
+            | InlineElement::Code(text)
+            | InlineElement::Math { source: text, .. }
+            | InlineElement::Emoji { emoji: text, .. } => text.clone(),
+            InlineElement::Emphasis(_, line)
+            | InlineElement::Strikethrough(line)
+            | InlineElement::Highlight(line)
+            | InlineElement::Superscript(line)
+            | InlineElement::Subscript(line)
+            | InlineElement::Link { text: line, .. } => line.to_plain_text(),
+            InlineElement::Image { alt, .. } => alt.to_plain_text(),
+            InlineElement::SoftBreak | InlineElement::HardBreak => "\n".to_string(),
+            InlineElement::Html(_) | InlineElement::FootnoteReference { .. } => String::new(),
+        }
+    }
+}
+
+impl Document {
+    /// Calls `f` on every block in the document and its footnotes, including nested blocks
+    pub fn for_each_block_mut(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        for element in self.elements_mut() {
+            element.for_each_block_mut(f);
+        }
+    }
+
+    /// Calls `f` on every line of inline content in the document and its footnotes
+    pub fn for_each_line_mut(&mut self, f: &mut dyn FnMut(&mut Line)) {
+        for element in self.elements_mut() {
+            element.for_each_line_mut(f);
+        }
+    }
+
+    pub fn for_each_block(&self, f: &mut dyn FnMut(&Element)) {
+        for element in self.elements() {
+            element.for_each_block(f);
+        }
+    }
+
+    pub fn for_each_line(&self, f: &mut dyn FnMut(&Line)) {
+        for element in self.elements() {
+            element.for_each_line(f);
+        }
+    }
+
+    /// The top level blocks of the document, followed by those of its footnotes
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        let footnote_elements = self
+            .footnotes
+            .iter()
+            .flat_map(|footnote| &footnote.elements);
+        self.elements.iter().chain(footnote_elements)
+    }
+
+    fn elements_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        let footnote_elements = self
+            .footnotes
+            .iter_mut()
+            .flat_map(|footnote| &mut footnote.elements);
+        self.elements.iter_mut().chain(footnote_elements)
+    }
+}
+
 impl fmt::Display for Document {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         for element in self.elements.iter() {
@@ -54,8 +481,8 @@ impl fmt::Display for Line {
 impl fmt::Display for Element {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Element::Header(level, line) => writeln!(f, "Header[{}] {}", level, line),
-            Element::Paragraph(lines) => writeln!(
+            Element::Header { level, line, .. } => writeln!(f, "Header[{}] {}", level, line),
+            Element::Paragraph(lines, _) => writeln!(
                 f,
                 "{}",
                 lines
@@ -64,6 +491,97 @@ impl fmt::Display for Element {
                     .collect::<Vec<String>>()
                     .join(" ")
             ),
+            Element::ThematicBreak => writeln!(f, "---"),
+            Element::CodeBlock { info, lines, .. } => {
+                writeln!(f, "CodeBlock[{}]", info)?;
+                for line in lines {
+                    writeln!(f, "{}", line)?;
+                }
+
+                Ok(())
+            }
+            Element::List {
+                ordered,
+                start,
+                items,
+                ..
+            } => {
+                for (index, item) in items.iter().enumerate() {
+                    if *ordered {
+                        write!(f, "{}. ", *start as usize + index)?;
+                    } else {
+                        write!(f, "- ")?;
+                    }
+
+                    if let ListItem::Task { checked, .. } = item {
+                        write!(f, "[{}] ", if *checked { "x" } else { " " })?;
+                    }
+
+                    for element in item.elements() {
+                        write!(f, "{}", element)?;
+                    }
+                }
+
+                Ok(())
+            }
+            Element::BlockQuote(elements) => {
+                for element in elements {
+                    write!(f, "> {}", element)?;
+                }
+
+                Ok(())
+            }
+            Element::Admonition { kind, title, body } => {
+                writeln!(f, "Admonition[{}] {}", kind, title)?;
+                for element in body {
+                    write!(f, "{}", element)?;
+                }
+
+                Ok(())
+            }
+            Element::Table { header, rows, .. } => {
+                for row in std::iter::once(header).chain(rows.iter()) {
+                    let cells = row
+                        .iter()
+                        .map(|cell| format!("{}", cell))
+                        .collect::<Vec<String>>();
+                    writeln!(f, "| {} |", cells.join(" | "))?;
+                }
+
+                Ok(())
+            }
+            Element::DefinitionList { items, .. } => {
+                for item in items {
+                    for term in &item.terms {
+                        writeln!(f, "{}", term)?;
+                    }
+
+                    for definition in &item.definitions {
+                        write!(f, ": ")?;
+                        for element in definition {
+                            write!(f, "{}", element)?;
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+            Element::MathBlock { source, .. } => writeln!(f, "MathBlock({})", source),
+            Element::HtmlBlock(lines) => {
+                for line in lines {
+                    writeln!(f, "{}", line)?;
+                }
+
+                Ok(())
+            }
+            Element::FootnoteDefinition { label, elements } => {
+                write!(f, "[^{}]: ", label)?;
+                for element in elements {
+                    write!(f, "{}", element)?;
+                }
+
+                Ok(())
+            }
         }
     }
 }
@@ -72,7 +590,22 @@ impl fmt::Display for InlineElement {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             InlineElement::Text(s) => write!(f, "{}", s),
+            InlineElement::Code(s) => write!(f, "Code({})", s),
             InlineElement::Emphasis(level, line) => write!(f, "Emphasis[{}]({})", level, line),
+            InlineElement::Strikethrough(line) => write!(f, "Strikethrough({})", line),
+            InlineElement::Highlight(line) => write!(f, "Highlight({})", line),
+            InlineElement::Superscript(line) => write!(f, "Superscript({})", line),
+            InlineElement::Subscript(line) => write!(f, "Subscript({})", line),
+            InlineElement::Html(html) => write!(f, "Html({})", html),
+            InlineElement::SoftBreak => write!(f, "SoftBreak"),
+            InlineElement::HardBreak => write!(f, "HardBreak"),
+            InlineElement::Math { source, .. } => write!(f, "Math({})", source),
+            InlineElement::Emoji { emoji, .. } => write!(f, "{}", emoji),
+            InlineElement::Link { text, url, .. } => write!(f, "Link[{}]({})", url, text),
+            InlineElement::Image { alt, url, .. } => write!(f, "Image[{}]({})", url, alt),
+            InlineElement::FootnoteReference { label, .. } => {
+                write!(f, "FootnoteReference[{}]", label)
+            }
         }
     }
 }
@@ -80,6 +613,7 @@ impl fmt::Display for InlineElement {
 #[cfg(test)]
 mod test {
     use crate::markdown::document::{InlineElement, Line};
+    use crate::util::html::Attributes;
 
     #[test]
     fn line_fmt() {
@@ -91,12 +625,12 @@ mod test {
                 .collect::<Vec<String>>()
                 .join("");
 
-            let line = Line {
-                elements: strings
+            let line = Line::new(
+                strings
                     .iter()
                     .map(|s| InlineElement::Text(s.to_string()))
                     .collect(),
-            };
+            );
 
             assert_eq!(format!("{}", line), expected_line);
             assert_eq!(line.to_string(), expected_line);
@@ -121,4 +655,26 @@ mod test {
         assert_text("🍩🍔🎸💆🎵🌼 🌄");
         assert_text("満クぜひ参時");
     }
+
+    #[test]
+    fn line_to_plain_text() {
+        let line = Line::new(vec![
+            InlineElement::Text("See ".to_string()),
+            InlineElement::Link {
+                text: Line::new(vec![InlineElement::Emphasis(2, Line::from_str("the docs"))]),
+                url: "https://example.com".to_string(),
+                title: None,
+                attributes: Attributes::default(),
+            },
+            InlineElement::Text(" and ".to_string()),
+            InlineElement::Image {
+                alt: Line::from_str("a diagram"),
+                url: "diagram.png".to_string(),
+                title: Some("Diagram".to_string()),
+                attributes: Attributes::default(),
+            },
+        ]);
+
+        assert_eq!(line.to_plain_text(), "See the docs and a diagram");
+    }
 }
@@ -0,0 +1,467 @@
+use crate::markdown::document::{Document, InlineElement, Line};
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use std::mem;
+
+/// Replaces `:shortcode:` sequences in text with emoji from `EMOJI`. Unknown shortcodes are left
+/// as they are, as are shortcodes in URLs, while code is never changed.
+pub fn expand_emoji_shortcodes(document: &mut Document) {
+    document.for_each_line_mut(&mut expand_in_line);
+}
+
+fn expand_in_line(line: &mut Line) {
+    for element in mem::take(&mut line.elements) {
+        match element {
+            InlineElement::Text(text) => line.elements.extend(expand_in_text(&text)),
+            mut element => {
+                match &mut element {
+                    InlineElement::Emphasis(_, inner)
+                    | InlineElement::Strikethrough(inner)
+                    | InlineElement::Highlight(inner)
+                    | InlineElement::Superscript(inner)
+                    | InlineElement::Subscript(inner)
+                    | InlineElement::Link { text: inner, .. }
+                    | InlineElement::Image { alt: inner, .. } => expand_in_line(inner),
+                    _ => {}
+                }
+
+                line.elements.push(element);
+            }
+        }
+    }
+}
+
+fn expand_in_text(text: &str) -> Vec<InlineElement> {
+    lazy_static! {
+        static ref SHORTCODE_PATTERN: Regex = Regex::new(r":([a-z0-9_+-]+):").unwrap();
+    }
+
+    let mut elements = vec![];
+    let mut text_start = 0;
+    let mut search_start = 0;
+
+    while let Ok(Some(caps)) = SHORTCODE_PATTERN.captures_from_pos(text, search_start) {
+        let whole = caps.get(0).unwrap();
+        let shortcode = caps.get(1).unwrap().as_str();
+
+        match lookup_emoji(shortcode) {
+            Some(emoji) if !in_url(text, whole.start(), whole.end()) => {
+                if whole.start() > text_start {
+                    elements.push(InlineElement::Text(
+                        text[text_start..whole.start()].to_string(),
+                    ));
+                }
+                elements.push(InlineElement::Emoji {
+                    shortcode: shortcode.to_string(),
+                    emoji: emoji.to_string(),
+                });
+                text_start = whole.end();
+                search_start = whole.end();
+            }
+            // The closing colon may start the next shortcode, as in `:not_one:smile:`
+            _ => search_start = whole.end() - 1,
+        }
+    }
+
+    // Text without any emoji is kept as it is, even if it's empty
+    if text_start < text.len() || elements.is_empty() {
+        elements.push(InlineElement::Text(text[text_start..].to_string()));
+    }
+
+    elements
+}
+
+/// Returns true if the text between `start` and `end` is part of a URL, which is found by looking
+/// at the word around it
+fn in_url(text: &str, start: usize, end: usize) -> bool {
+    let word_start = text[..start]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(index, c)| index + c.len_utf8());
+    let word_end = text[end..]
+        .find(char::is_whitespace)
+        .map_or(text.len(), |index| end + index);
+    let word = &text[word_start..word_end];
+
+    word.contains("://") || word.starts_with("www.") || word.starts_with("mailto:")
+}
+
+pub fn lookup_emoji(shortcode: &str) -> Option<&'static str> {
+    EMOJI
+        .binary_search_by_key(&shortcode, |(shortcode, _)| shortcode)
+        .ok()
+        .map(|index| EMOJI[index].1)
+}
+
+/// Shortcodes and their emoji, sorted by shortcode. These are the commonly used shortcodes from
+/// GitHub.
+const EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("1234", "🔢"),
+    ("alarm_clock", "⏰"),
+    ("alien", "👽"),
+    ("angel", "👼"),
+    ("anger", "💢"),
+    ("angry", "😠"),
+    ("ant", "🐜"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("art", "🎨"),
+    ("astonished", "😲"),
+    ("baby", "👶"),
+    ("balloon", "🎈"),
+    ("banana", "🍌"),
+    ("bangbang", "‼️"),
+    ("bank", "🏦"),
+    ("bar_chart", "📊"),
+    ("battery", "🔋"),
+    ("bear", "🐻"),
+    ("beer", "🍺"),
+    ("beers", "🍻"),
+    ("beetle", "🐞"),
+    ("bell", "🔔"),
+    ("bike", "🚲"),
+    ("bird", "🐦"),
+    ("birthday", "🎂"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bowtie", "🎀"),
+    ("brain", "🧠"),
+    ("bread", "🍞"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("bus", "🚌"),
+    ("cactus", "🌵"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("car", "🚗"),
+    ("cat", "🐱"),
+    ("chart_with_downwards_trend", "📉"),
+    ("chart_with_upwards_trend", "📈"),
+    ("cherries", "🍒"),
+    ("chicken", "🐔"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("clock", "🕐"),
+    ("closed_lock_with_key", "🔐"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confetti_ball", "🎊"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cookie", "🍪"),
+    ("cool", "🆒"),
+    ("cow", "🐮"),
+    ("crab", "🦀"),
+    ("crown", "👑"),
+    ("cry", "😢"),
+    ("crystal_ball", "🔮"),
+    ("dart", "🎯"),
+    ("dash", "💨"),
+    ("disappointed", "😞"),
+    ("dizzy", "💫"),
+    ("dog", "🐶"),
+    ("dollar", "💵"),
+    ("dolphin", "🐬"),
+    ("door", "🚪"),
+    ("dragon", "🐉"),
+    ("droplet", "💧"),
+    ("earth_africa", "🌍"),
+    ("earth_americas", "🌎"),
+    ("earth_asia", "🌏"),
+    ("egg", "🥚"),
+    ("eyes", "👀"),
+    ("facepalm", "🤦"),
+    ("fire", "🔥"),
+    ("fish", "🐟"),
+    ("fist", "✊"),
+    ("flag_white", "🏳️"),
+    ("flashlight", "🔦"),
+    ("floppy_disk", "💾"),
+    ("flushed", "😳"),
+    ("fox_face", "🦊"),
+    ("frog", "🐸"),
+    ("gear", "⚙️"),
+    ("gem", "💎"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("green_heart", "💚"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠️"),
+    ("hand", "✋"),
+    ("hankey", "💩"),
+    ("hear_no_evil", "🙉"),
+    ("heart", "❤️"),
+    ("heart_eyes", "😍"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("hushed", "😯"),
+    ("information_source", "ℹ️"),
+    ("innocent", "😇"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("keyboard", "⌨️"),
+    ("kiss", "💋"),
+    ("koala", "🐨"),
+    ("label", "🏷️"),
+    ("laughing", "😆"),
+    ("leaves", "🍃"),
+    ("link", "🔗"),
+    ("lipstick", "💄"),
+    ("lock", "🔒"),
+    ("loudspeaker", "📢"),
+    ("love_letter", "💌"),
+    ("mag", "🔍"),
+    ("mailbox", "📫"),
+    ("memo", "📝"),
+    ("microscope", "🔬"),
+    ("money_with_wings", "💸"),
+    ("monkey", "🐒"),
+    ("moon", "🌙"),
+    ("mortar_board", "🎓"),
+    ("mouse", "🐭"),
+    ("muscle", "💪"),
+    ("mushroom", "🍄"),
+    ("musical_note", "🎵"),
+    ("nerd_face", "🤓"),
+    ("neutral_face", "😐"),
+    ("new", "🆕"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("notebook", "📓"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("open_mouth", "😮"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("palm_tree", "🌴"),
+    ("panda_face", "🐼"),
+    ("paperclip", "📎"),
+    ("partying_face", "🥳"),
+    ("pencil", "📝"),
+    ("pencil2", "✏️"),
+    ("penguin", "🐧"),
+    ("pensive", "😔"),
+    ("phone", "☎️"),
+    ("pig", "🐷"),
+    ("pill", "💊"),
+    ("pizza", "🍕"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("police_car", "🚓"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rabbit", "🐰"),
+    ("racehorse", "🐎"),
+    ("radio", "📻"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("relaxed", "☺️"),
+    ("relieved", "😌"),
+    ("rescue_worker_helmet", "⛑️"),
+    ("rewind", "⏪"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("rose", "🌹"),
+    ("rotating_light", "🚨"),
+    ("runner", "🏃"),
+    ("sad", "😞"),
+    ("satellite", "📡"),
+    ("scissors", "✂️"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("seedling", "🌱"),
+    ("shield", "🛡️"),
+    ("shrug", "🤷"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("snail", "🐌"),
+    ("snake", "🐍"),
+    ("snowflake", "❄️"),
+    ("snowman", "⛄"),
+    ("sob", "😭"),
+    ("soccer", "⚽"),
+    ("sparkles", "✨"),
+    ("sparkling_heart", "💖"),
+    ("speak_no_evil", "🙊"),
+    ("speech_balloon", "💬"),
+    ("spider", "🕷️"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("stars", "🌠"),
+    ("stopwatch", "⏱️"),
+    ("straight_ruler", "📏"),
+    ("strawberry", "🍓"),
+    ("sun_with_face", "🌞"),
+    ("sunflower", "🌻"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("sweat", "😓"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("taxi", "🚕"),
+    ("tea", "🍵"),
+    ("telescope", "🔭"),
+    ("tent", "⛺"),
+    ("thinking", "🤔"),
+    ("thought_balloon", "💭"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("ticket", "🎫"),
+    ("tiger", "🐯"),
+    ("tired_face", "😫"),
+    ("toilet", "🚽"),
+    ("tongue", "👅"),
+    ("tools", "🛠️"),
+    ("tophat", "🎩"),
+    ("tractor", "🚜"),
+    ("traffic_light", "🚥"),
+    ("train", "🚋"),
+    ("trophy", "🏆"),
+    ("truck", "🚚"),
+    ("tulip", "🌷"),
+    ("turtle", "🐢"),
+    ("tv", "📺"),
+    ("umbrella", "☔"),
+    ("unamused", "😒"),
+    ("unicorn", "🦄"),
+    ("unlock", "🔓"),
+    ("upside_down_face", "🙃"),
+    ("v", "✌️"),
+    ("vertical_traffic_light", "🚦"),
+    ("volcano", "🌋"),
+    ("warning", "⚠️"),
+    ("watch", "⌚"),
+    ("watermelon", "🍉"),
+    ("wave", "👋"),
+    ("whale", "🐳"),
+    ("wheelchair", "♿"),
+    ("white_check_mark", "✅"),
+    ("wine_glass", "🍷"),
+    ("wink", "😉"),
+    ("wolf", "🐺"),
+    ("worried", "😟"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yellow_heart", "💛"),
+    ("yum", "😋"),
+    ("zap", "⚡"),
+    ("zipper_mouth_face", "🤐"),
+    ("zzz", "💤"),
+];
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::document::{InlineElement, Line};
+    use crate::markdown::emoji::{expand_in_text, EMOJI};
+    use crate::markdown::parser::parse;
+
+    fn emoji(shortcode: &str, emoji: &str) -> InlineElement {
+        InlineElement::Emoji {
+            shortcode: shortcode.to_string(),
+            emoji: emoji.to_string(),
+        }
+    }
+
+    fn text(text: &str) -> InlineElement {
+        InlineElement::Text(text.to_string())
+    }
+
+    #[test]
+    fn test_emoji_table_is_sorted() {
+        assert!(EMOJI.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_expand_in_text() {
+        assert_eq!(
+            expand_in_text(":tada: Released:+1::rocket:"),
+            vec![
+                emoji("tada", "🎉"),
+                text(" Released"),
+                emoji("+1", "👍"),
+                emoji("rocket", "🚀"),
+            ]
+        );
+        assert_eq!(
+            expand_in_text("At 12:30:45 :not_an_emoji:smile:"),
+            vec![text("At 12:30:45 :not_an_emoji"), emoji("smile", "😄")]
+        );
+        assert_eq!(
+            expand_in_text("See https://example.com/:smile:/ and www.example.com/:x:"),
+            vec![text(
+                "See https://example.com/:smile:/ and www.example.com/:x:"
+            )]
+        );
+
+        // Whitespace before a shortcode may take more than one byte
+        assert_eq!(
+            expand_in_text("Voil\u{e0}\u{a0}:smile:"),
+            vec![text("Voil\u{e0}\u{a0}"), emoji("smile", "😄")]
+        );
+        assert_eq!(
+            expand_in_text("\u{3000}:smile:\u{3000}https://example.com/:smile:"),
+            vec![
+                text("\u{3000}"),
+                emoji("smile", "😄"),
+                text("\u{3000}https://example.com/:smile:"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_emoji_shortcodes() {
+        let mut document = parse("*:fire:* `:fire:` [:fire:](https://example.com/:fire:)");
+
+        let mut lines = vec![];
+        document.elements[0].for_each_line_mut(&mut |line| lines.push(line.clone()));
+
+        assert_eq!(
+            lines,
+            vec![Line::new(vec![
+                InlineElement::Emphasis(1, Line::new(vec![emoji("fire", "🔥")])),
+                text(" "),
+                InlineElement::Code(":fire:".to_string()),
+                text(" "),
+                InlineElement::Link {
+                    text: Line::new(vec![emoji("fire", "🔥")]),
+                    url: "https://example.com/:fire:".to_string(),
+                    title: None,
+                    attributes: Default::default(),
+                },
+            ])]
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::markdown::document::{Document, Element, Footnote, InlineElement};
+use crate::markdown::parser::normalize_label;
+use std::collections::HashMap;
+use std::mem;
+
+/// Moves footnote definitions out of the document and numbers them in order of first reference.
+/// References without a definition are turned back into text, and unreferenced definitions are
+/// dropped.
+pub fn resolve_footnotes(document: &mut Document) {
+    let mut definitions = HashMap::new();
+    take_definitions(&mut document.elements, &mut definitions);
+
+    let mut footnotes = vec![];
+    for element in &mut document.elements {
+        number_references(element, &mut definitions, &mut footnotes);
+    }
+
+    // Footnotes may reference other footnotes, which are numbered after all of the others
+    let mut index = 0;
+    while index < footnotes.len() {
+        let mut elements = mem::take(&mut footnotes[index].elements);
+        for element in &mut elements {
+            number_references(element, &mut definitions, &mut footnotes);
+        }
+
+        footnotes[index].elements = elements;
+        index += 1;
+    }
+
+    document.footnotes = footnotes;
+}
+
+/// Removes footnote definitions from the elements, including from nested blocks. The first
+/// definition of a label wins.
+fn take_definitions(elements: &mut Vec<Element>, definitions: &mut HashMap<String, Footnote>) {
+    for mut element in mem::take(elements) {
+        match &mut element {
+            Element::BlockQuote(children) | Element::Admonition { body: children, .. } => {
+                take_definitions(children, definitions)
+            }
+            Element::List { items, .. } => {
+                for item in items {
+                    take_definitions(item.elements_mut(), definitions);
+                }
+            }
+            Element::DefinitionList { items, .. } => {
+                for definition in items.iter_mut().flat_map(|item| &mut item.definitions) {
+                    take_definitions(definition, definitions);
+                }
+            }
+            _ => {}
+        }
+
+        match element {
+            Element::FootnoteDefinition {
+                label,
+                elements: mut children,
+            } => {
+                take_definitions(&mut children, definitions);
+
+                definitions
+                    .entry(normalize_label(&label))
+                    .or_insert(Footnote {
+                        label,
+                        elements: children,
+                        references: 0,
+                    });
+            }
+            _ => elements.push(element),
+        }
+    }
+}
+
+fn number_references(
+    element: &mut Element,
+    definitions: &mut HashMap<String, Footnote>,
+    footnotes: &mut Vec<Footnote>,
+) {
+    element.for_each_line_mut(&mut |line| {
+        line.for_each_element_mut(&mut |inline| {
+            let label = match inline {
+                InlineElement::FootnoteReference { label, .. } => label.clone(),
+                _ => return,
+            };
+
+            let key = normalize_label(&label);
+            let index = match footnotes
+                .iter()
+                .position(|footnote| normalize_label(&footnote.label) == key)
+            {
+                Some(index) => index,
+                None => match definitions.remove(&key) {
+                    Some(footnote) => {
+                        footnotes.push(footnote);
+                        footnotes.len() - 1
+                    }
+                    None => {
+                        *inline = InlineElement::Text(format!("[^{}]", label));
+                        return;
+                    }
+                },
+            };
+
+            footnotes[index].references += 1;
+            *inline = InlineElement::FootnoteReference {
+                label,
+                number: index as u32 + 1,
+                occurrence: footnotes[index].references,
+            };
+        });
+    });
+}
@@ -0,0 +1,110 @@
+use crate::markdown::document::{Document, Element, Line};
+use std::collections::HashSet;
+
+/// Gives every header in the document without an id one made from its text. Ids are unique within
+/// the document, with repeated ones suffixed by `-1`, `-2` and so on.
+pub fn assign_header_ids(document: &mut Document) {
+    let mut used_ids = HashSet::new();
+
+    // Ids given with attributes are kept as they are, so generated ones must avoid them
+    for_each_header(document, &mut |id, _| {
+        if !id.is_empty() {
+            used_ids.insert(id.clone());
+        }
+    });
+
+    for_each_header(document, &mut |id, line| {
+        if !id.is_empty() {
+            return;
+        }
+
+        let slug = slugify(&line.to_plain_text());
+
+        *id = slug.clone();
+        let mut suffix = 0;
+        while !used_ids.insert(id.clone()) {
+            suffix += 1;
+            *id = format!("{}-{}", slug, suffix);
+        }
+    });
+}
+
+/// Calls `f` on the id and text of every header, including headers in nested blocks and footnotes
+fn for_each_header(document: &mut Document, f: &mut dyn FnMut(&mut String, &Line)) {
+    document.for_each_block_mut(&mut |block| {
+        if let Element::Header { id, line, .. } = block {
+            f(id, line);
+        }
+    });
+}
+
+/// Turns text into a lowercase slug for use in URLs, keeping letters and numbers from any script.
+/// Whitespace becomes hyphens and other punctuation is removed.
+pub fn slugify(text: &str) -> String {
+    let slug = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("-");
+
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::document::{Document, Element, Line};
+    use crate::markdown::headers::{assign_header_ids, slugify};
+    use crate::util::html::Attributes;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Multiple   spaces "), "multiple-spaces");
+        assert_eq!(slugify("snake_case & kebab-case"), "snake_case--kebab-case");
+        assert_eq!(slugify("Ünïcödé Straße"), "ünïcödé-straße");
+        assert_eq!(slugify("日本語の見出し"), "日本語の見出し");
+        assert_eq!(slugify("?!"), "section");
+    }
+
+    #[test]
+    fn test_assign_header_ids() {
+        let header = |id: &str, text: &str| Element::Header {
+            level: 1,
+            id: id.to_string(),
+            line: Line::from_str(text),
+            attributes: Attributes::default(),
+        };
+
+        let mut document = Document {
+            elements: vec![
+                header("", "Intro"),
+                header("", "Intro"),
+                header("", "Intro 1"),
+                Element::BlockQuote(vec![header("", "Intro")]),
+                header("intro-3", "Explicit"),
+            ],
+            footnotes: vec![],
+        };
+        assign_header_ids(&mut document);
+
+        assert_eq!(
+            document.elements,
+            vec![
+                header("intro", "Intro"),
+                header("intro-1", "Intro"),
+                header("intro-1-1", "Intro 1"),
+                Element::BlockQuote(vec![header("intro-2", "Intro")]),
+                header("intro-3", "Explicit"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1146 @@
+use crate::markdown::attributes::parse_attributes;
+use crate::markdown::document::{InlineElement, Line};
+use crate::markdown::parser::normalize_label;
+use crate::markdown::references::LinkReferences;
+use crate::util::html::{decode_entity, Attributes};
+use crate::util::regex_split::split_by_regex;
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use std::mem;
+
+/// An HTML open tag such as `<a href="/">`, which is also used to find HTML blocks
+pub const HTML_OPEN_TAG_PATTERN: &str = r#"<[A-Za-z][A-Za-z0-9-]*(?:\s+[A-Za-z_:][A-Za-z0-9_.:-]*(?:\s*=\s*(?:[^\s"'=<>`]+|'[^']*'|"[^"]*"))?)*\s*/?>"#;
+
+/// An HTML closing tag such as `</a>`
+pub const HTML_CLOSING_TAG_PATTERN: &str = r"</[A-Za-z][A-Za-z0-9-]*\s*>";
+
+/// An inline item that has not been resolved into its final element yet
+#[derive(Debug)]
+enum Item {
+    Text(String),
+    /// The opening bracket of a potential link (`[`) or image (`![`)
+    Bracket {
+        image: bool,
+    },
+    Delimiter(Delimiter),
+    Element(InlineElement),
+}
+
+/// A run of delimiter characters (such as `**` or `~~`) that may open or close emphasis
+#[derive(Debug)]
+struct Delimiter {
+    character: char,
+    /// The number of characters that have not been used up by emphasis yet
+    count: usize,
+    original_count: usize,
+    can_open: bool,
+    can_close: bool,
+}
+
+#[derive(Debug)]
+struct BracketEntry {
+    /// The index of the bracket's item
+    index: usize,
+    /// Byte offset of the text after the bracket
+    text_start: usize,
+    image: bool,
+    /// Brackets before a link are deactivated, since links cannot contain other links
+    active: bool,
+}
+
+/// Parses inline elements following the CommonMark algorithm, using a delimiter stack for
+/// emphasis and a bracket stack for links and images. Reference links are resolved using
+/// `references`.
+pub fn parse_inlines(text: &str, references: &LinkReferences) -> Line {
+    let mut parser = InlineParser {
+        text,
+        references,
+        position: 0,
+        items: vec![],
+        brackets: vec![],
+    };
+
+    parser.parse();
+    process_emphasis(&mut parser.items, 0);
+
+    items_to_line(parser.items)
+}
+
+struct InlineParser<'a> {
+    text: &'a str,
+    references: &'a LinkReferences,
+    /// Byte offset of the next character to parse
+    position: usize,
+    items: Vec<Item>,
+    brackets: Vec<BracketEntry>,
+}
+
+impl<'a> InlineParser<'a> {
+    fn parse(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                '\n' => self.parse_line_break(),
+                '\\' if self.rest().starts_with("\\\n") => self.parse_line_break(),
+                '\\' => self.parse_backslash(),
+                '&' => self.parse_entity(),
+                '`' => self.parse_code_span(),
+                '$' => self.parse_math(),
+                '*' | '_' | '~' | '=' | '^' => self.parse_delimiter_run(c),
+                '<' => self.parse_autolink(),
+                '[' if self.rest().starts_with("[^") => self.parse_footnote_reference(),
+                '[' => self.open_bracket(false),
+                '!' if self.rest().starts_with("![") => self.open_bracket(true),
+                ']' => self.close_bracket(),
+                _ => {
+                    self.push_text(&c.to_string());
+                    self.position += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn push_text(&mut self, s: &str) {
+        match self.items.last_mut() {
+            Some(Item::Text(text)) => text.push_str(s),
+            _ => self.items.push(Item::Text(s.to_string())),
+        }
+    }
+
+    fn parse_backslash(&mut self) {
+        match self.rest().chars().nth(1) {
+            Some(c) if c.is_ascii_punctuation() => {
+                self.push_text(&c.to_string());
+                self.position += 1 + c.len_utf8();
+            }
+            _ => {
+                self.push_text("\\");
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Parses the end of a line, which is a hard break after two or more spaces or a backslash and a
+    /// soft break otherwise. Spaces on either side of the break are dropped.
+    fn parse_line_break(&mut self) {
+        let mut hard = self.rest().starts_with('\\');
+        if hard {
+            self.position += 1;
+        } else if let Some(Item::Text(text)) = self.items.last_mut() {
+            let trimmed_length = text.trim_end_matches(' ').len();
+            hard = text.len() - trimmed_length >= 2;
+            text.truncate(trimmed_length);
+            if text.is_empty() {
+                self.items.pop();
+            }
+        }
+
+        self.position += 1;
+        self.position += count_leading(self.rest(), ' ');
+        self.items.push(Item::Element(if hard {
+            InlineElement::HardBreak
+        } else {
+            InlineElement::SoftBreak
+        }));
+    }
+
+    fn parse_entity(&mut self) {
+        lazy_static! {
+            static ref ENTITY_PATTERN: Regex =
+                Regex::new(r"^&(?:#[xX][0-9a-fA-F]{1,6}|#[0-9]{1,7}|[A-Za-z][A-Za-z0-9]{1,31});")
+                    .unwrap();
+        }
+
+        if let Ok(Some(m)) = ENTITY_PATTERN.find(self.rest()) {
+            if let Some(decoded) = decode_entity(m.as_str()) {
+                self.push_text(&decoded);
+                self.position += m.end();
+                return;
+            }
+        }
+
+        self.push_text("&");
+        self.position += 1;
+    }
+
+    fn parse_code_span(&mut self) {
+        let rest = self.rest();
+        let run_length = count_leading(rest, '`');
+
+        // Find a closing run of exactly the same length
+        let mut search_start = run_length;
+        while let Some(offset) = rest[search_start..].find('`') {
+            let closing_start = search_start + offset;
+            let closing_length = count_leading(&rest[closing_start..], '`');
+
+            if closing_length == run_length {
+                let code = rest[run_length..closing_start].replace('\n', " ");
+
+                // A single leading and trailing space is stripped so that code can begin or end
+                // with a backtick, unless the code consists entirely of spaces.
+                let code = if code.len() >= 2
+                    && code.starts_with(' ')
+                    && code.ends_with(' ')
+                    && !code.trim().is_empty()
+                {
+                    code[1..code.len() - 1].to_string()
+                } else {
+                    code
+                };
+
+                self.items.push(Item::Element(InlineElement::Code(code)));
+                self.position += closing_start + closing_length;
+                return;
+            }
+
+            search_start = closing_start + closing_length;
+        }
+
+        // Without a closing run, the backticks are literal text
+        self.push_text(&rest[..run_length]);
+        self.position += run_length;
+    }
+
+    /// Parses inline math (`$x$`) or display math (`$$x$$`), following Pandoc's rules so that
+    /// prices aren't mistaken for math. The math cannot start or end with a space, and inline math
+    /// cannot start with a digit or be followed by one. The math ends at the first delimiter that
+    /// can close it and isn't escaped with a backslash.
+    fn parse_math(&mut self) {
+        let rest = self.rest();
+        let display = rest.starts_with("$$");
+        let delimiter = if display { "$$" } else { "$" };
+        let start = delimiter.len();
+
+        let is_digit = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+        if rest[start..].starts_with(char::is_whitespace) || (!display && is_digit(&rest[start..]))
+        {
+            self.push_text(delimiter);
+            self.position += start;
+            return;
+        }
+
+        let mut end = start;
+        while let Some(c) = rest[end..].chars().next() {
+            if c == '\\' {
+                end += 1 + rest[end + 1..].chars().next().map_or(0, char::len_utf8);
+                continue;
+            }
+
+            if rest[end..].starts_with(delimiter) {
+                let source = &rest[start..end];
+                let valid = !source.is_empty()
+                    && !source.ends_with(char::is_whitespace)
+                    && (display || !is_digit(&rest[end + 1..]));
+                if valid {
+                    self.items.push(Item::Element(InlineElement::Math {
+                        source: source.to_string(),
+                        display,
+                    }));
+                    self.position += end + delimiter.len();
+                    return;
+                }
+            }
+
+            end += c.len_utf8();
+        }
+
+        self.push_text(delimiter);
+        self.position += start;
+    }
+
+    fn parse_autolink(&mut self) {
+        lazy_static! {
+            static ref URI_AUTOLINK_PATTERN: Regex =
+                Regex::new(r"^<([A-Za-z][A-Za-z0-9+.-]{1,31}:[^<>\x00-\x20]*)>").unwrap();
+            static ref EMAIL_AUTOLINK_PATTERN: Regex = Regex::new(
+                r"^<([a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*)>"
+            )
+            .unwrap();
+        }
+
+        let (address, url, length) =
+            if let Ok(Some(caps)) = URI_AUTOLINK_PATTERN.captures(self.rest()) {
+                let uri = caps.get(1).unwrap().as_str();
+                (uri, uri.to_string(), caps.get(0).unwrap().end())
+            } else if let Ok(Some(caps)) = EMAIL_AUTOLINK_PATTERN.captures(self.rest()) {
+                let email = caps.get(1).unwrap().as_str();
+                (
+                    email,
+                    format!("mailto:{}", email),
+                    caps.get(0).unwrap().end(),
+                )
+            } else {
+                return self.parse_inline_html();
+            };
+
+        self.items.push(Item::Element(InlineElement::Link {
+            text: Line::from_str(address),
+            url,
+            title: None,
+            attributes: Attributes::default(),
+        }));
+        self.position += length;
+    }
+
+    /// Parses a tag, comment, processing instruction, declaration or CDATA section, which are
+    /// passed through as raw HTML
+    fn parse_inline_html(&mut self) {
+        lazy_static! {
+            static ref INLINE_HTML_PATTERN: Regex = Regex::new(&format!(
+                r"^(?:{}|{}|<!--(?!-?>)(?:[^-]|-(?!-))*-->|<\?.*?\?>|<![A-Za-z][^>]*>|<!\[CDATA\[.*?\]\]>)",
+                HTML_OPEN_TAG_PATTERN, HTML_CLOSING_TAG_PATTERN
+            ))
+            .unwrap();
+        }
+
+        match INLINE_HTML_PATTERN.find(self.rest()) {
+            Ok(Some(m)) => {
+                self.items
+                    .push(Item::Element(InlineElement::Html(m.as_str().to_string())));
+                self.position += m.end();
+            }
+            _ => {
+                self.push_text("<");
+                self.position += 1;
+            }
+        }
+    }
+
+    fn parse_delimiter_run(&mut self, character: char) {
+        let start = self.position;
+        let count = count_leading(self.rest(), character);
+        let end = start + count * character.len_utf8();
+
+        // Highlights are delimited by exactly two characters, superscripts by one and
+        // strikethroughs or subscripts by one or two
+        let valid = match character {
+            '=' => count == 2,
+            '^' => count == 1,
+            '~' => count <= 2,
+            _ => true,
+        };
+        if !valid {
+            self.push_text(&self.text[start..end]);
+            self.position = end;
+            return;
+        }
+
+        let before = self.text[..start].chars().next_back().unwrap_or(' ');
+        let after = self.text[end..].chars().next().unwrap_or(' ');
+
+        let left_flanking = !after.is_whitespace()
+            && (!is_punctuation(after) || before.is_whitespace() || is_punctuation(before));
+        let right_flanking = !before.is_whitespace()
+            && (!is_punctuation(before) || after.is_whitespace() || is_punctuation(after));
+
+        // Underscores cannot open or close emphasis inside of a word
+        let (can_open, can_close) = match character {
+            '_' => (
+                left_flanking && (!right_flanking || is_punctuation(before)),
+                right_flanking && (!left_flanking || is_punctuation(after)),
+            ),
+            _ => (left_flanking, right_flanking),
+        };
+
+        self.items.push(Item::Delimiter(Delimiter {
+            character,
+            count,
+            original_count: count,
+            can_open,
+            can_close,
+        }));
+        self.position = end;
+    }
+
+    fn parse_footnote_reference(&mut self) {
+        lazy_static! {
+            static ref FOOTNOTE_REFERENCE_PATTERN: Regex =
+                Regex::new(r"^\[\^([^\]\s]+)\]").unwrap();
+        }
+
+        match FOOTNOTE_REFERENCE_PATTERN.captures(self.rest()) {
+            Ok(Some(caps)) => {
+                self.items
+                    .push(Item::Element(InlineElement::FootnoteReference {
+                        label: caps.get(1).unwrap().as_str().to_string(),
+                        number: 0,
+                        occurrence: 0,
+                    }));
+                self.position += caps.get(0).unwrap().end();
+            }
+            _ => self.open_bracket(false),
+        }
+    }
+
+    fn open_bracket(&mut self, image: bool) {
+        self.position += if image { 2 } else { 1 };
+        self.brackets.push(BracketEntry {
+            index: self.items.len(),
+            text_start: self.position,
+            image,
+            active: true,
+        });
+        self.items.push(Item::Bracket { image });
+    }
+
+    /// Looks up a full (`[text][label]`), collapsed (`[label][]`) or shortcut (`[label]`)
+    /// reference following a link's text, returning the same as `parse_link_destination`.
+    fn parse_link_reference(&self, link_text: &str) -> Option<(String, Option<String>, usize)> {
+        lazy_static! {
+            static ref LABEL_PATTERN: Regex =
+                Regex::new(r"^\[((?:[^\[\]\\]|\\.){0,999})\]").unwrap();
+        }
+
+        let (label, length) = match LABEL_PATTERN.captures(self.rest()) {
+            Ok(Some(caps)) => {
+                let label = caps.get(1).unwrap().as_str();
+                let length = caps.get(0).unwrap().end();
+
+                if label.is_empty() {
+                    (link_text, length)
+                } else if label.trim().is_empty() {
+                    return None;
+                } else {
+                    (label, length)
+                }
+            }
+            _ => (link_text, 0),
+        };
+
+        let definition = self.references.get(&normalize_label(label))?;
+        Some((definition.url.clone(), definition.title.clone(), length))
+    }
+
+    fn close_bracket(&mut self) {
+        let text_end = self.position;
+        self.position += 1;
+
+        let bracket = match self.brackets.pop() {
+            Some(bracket) if bracket.active => bracket,
+            _ => {
+                self.push_text("]");
+                return;
+            }
+        };
+
+        let (url, title, length) = match parse_link_destination(self.rest())
+            .or_else(|| self.parse_link_reference(&self.text[bracket.text_start..text_end]))
+        {
+            Some(destination) => destination,
+            None => {
+                self.push_text("]");
+                return;
+            }
+        };
+        self.position += length;
+
+        let attributes = match parse_attributes(self.rest()) {
+            Some((attributes, length)) => {
+                self.position += length;
+                attributes
+            }
+            None => Attributes::default(),
+        };
+
+        process_emphasis(&mut self.items, bracket.index + 1);
+        let text = items_to_line(self.items.drain(bracket.index + 1..).collect());
+        self.items.pop();
+
+        if bracket.image {
+            self.items.push(Item::Element(InlineElement::Image {
+                alt: text,
+                url,
+                title,
+                attributes,
+            }));
+        } else {
+            self.items.push(Item::Element(InlineElement::Link {
+                text,
+                url,
+                title,
+                attributes,
+            }));
+
+            for bracket in self.brackets.iter_mut().filter(|bracket| !bracket.image) {
+                bracket.active = false;
+            }
+        }
+    }
+}
+
+/// Parses an inline link destination and optional title (e.g. `(/url "title")`), returning them
+/// along with the number of bytes consumed.
+fn parse_link_destination(text: &str) -> Option<(String, Option<String>, usize)> {
+    lazy_static! {
+        static ref DESTINATION_PATTERN: Regex = Regex::new(
+            r#"^\(\s*(?:<((?:[^<>\n\\]|\\.)*)>|((?:[^\s()\\]|\\.|\((?:[^\s()\\]|\\.)*\))*))(?:\s+(?:"((?:[^"\\]|\\.)*)"|'((?:[^'\\]|\\.)*)'|\(((?:[^()\\]|\\.)*)\)))?\s*\)"#
+        )
+        .unwrap();
+    }
+
+    let captures = DESTINATION_PATTERN.captures(text).ok()??;
+
+    let url = captures
+        .get(1)
+        .or(captures.get(2))
+        .map_or(String::new(), |m| unescape(m.as_str()));
+    let title = captures
+        .get(3)
+        .or(captures.get(4))
+        .or(captures.get(5))
+        .map(|m| unescape(m.as_str()));
+
+    Some((url, title, captures.get(0).unwrap().end()))
+}
+
+/// Turns bare URLs (`https://...` or `www....`) and email addresses in text into links, like GitHub
+/// Flavored Markdown's autolink extension. Text inside of links is left alone.
+pub fn link_bare_urls(line: &mut Line) {
+    lazy_static! {
+        static ref BARE_URL_PATTERN: Regex = Regex::new(
+            r"(?<![\w.+-])(?:(?:https?://|www\.)[\w-]+(?:\.[\w-]+)*[^\s<]*|[\w.+-]+@[\w-]+(?:\.[\w-]+)+)"
+        )
+        .unwrap();
+    }
+
+    for element in mem::take(&mut line.elements) {
+        match element {
+            InlineElement::Text(text) => {
+                let segments = split_by_regex(
+                    &text,
+                    &BARE_URL_PATTERN,
+                    |caps| bare_url_to_elements(caps.get(0).unwrap().as_str()),
+                    |text| vec![InlineElement::Text(text.to_string())],
+                );
+
+                for element in segments.into_iter().flatten() {
+                    match (line.elements.last_mut(), element) {
+                        (Some(InlineElement::Text(previous)), InlineElement::Text(text)) => {
+                            previous.push_str(&text)
+                        }
+                        (_, element) => line.elements.push(element),
+                    }
+                }
+            }
+            mut element => {
+                match &mut element {
+                    InlineElement::Emphasis(_, inner)
+                    | InlineElement::Strikethrough(inner)
+                    | InlineElement::Highlight(inner)
+                    | InlineElement::Superscript(inner)
+                    | InlineElement::Subscript(inner) => link_bare_urls(inner),
+                    _ => {}
+                }
+
+                line.elements.push(element);
+            }
+        }
+    }
+}
+
+/// Converts a matched bare URL into a link, followed by any trailing punctuation as text
+fn bare_url_to_elements(matched: &str) -> Vec<InlineElement> {
+    // Trailing punctuation is not part of the URL, and neither are unbalanced closing parentheses
+    let mut end = matched.len();
+    loop {
+        let url = &matched[..end];
+        match url.chars().last() {
+            Some('?' | '!' | '.' | ',' | ':' | '*' | '_' | '~' | '\'' | '"') => end -= 1,
+            Some(')') if url.matches(')').count() > url.matches('(').count() => end -= 1,
+            _ => break,
+        }
+    }
+
+    let (address, rest) = matched.split_at(end);
+    let url = if address.starts_with("www.") {
+        format!("http://{}", address)
+    } else if !address.contains("://") {
+        format!("mailto:{}", address)
+    } else {
+        address.to_string()
+    };
+
+    let mut elements = vec![InlineElement::Link {
+        text: Line::from_str(address),
+        url,
+        title: None,
+        attributes: Attributes::default(),
+    }];
+    if !rest.is_empty() {
+        elements.push(InlineElement::Text(rest.to_string()));
+    }
+
+    elements
+}
+
+/// Resolves backslash escapes and entity references in text that cannot contain other inlines,
+/// such as link destinations and titles
+pub fn unescape(text: &str) -> String {
+    let mut parser = InlineParser {
+        text,
+        references: &LinkReferences::new(),
+        position: 0,
+        items: vec![],
+        brackets: vec![],
+    };
+
+    while let Some(c) = parser.peek() {
+        match c {
+            '\\' => parser.parse_backslash(),
+            '&' => parser.parse_entity(),
+            _ => {
+                parser.push_text(&c.to_string());
+                parser.position += c.len_utf8();
+            }
+        }
+    }
+
+    items_to_line(parser.items).to_plain_text()
+}
+
+/// Matches up delimiter runs after `bottom` into emphasis, following CommonMark's "process
+/// emphasis" procedure. Unmatched delimiters are left in place and become text.
+fn process_emphasis(items: &mut Vec<Item>, bottom: usize) {
+    let mut closer_index = bottom;
+
+    while closer_index < items.len() {
+        let closer = match &items[closer_index] {
+            Item::Delimiter(closer) if closer.can_close => closer,
+            _ => {
+                closer_index += 1;
+                continue;
+            }
+        };
+
+        let opener_index = (bottom..closer_index)
+            .rev()
+            .find(|&index| match &items[index] {
+                Item::Delimiter(opener) => {
+                    opener.character == closer.character
+                        && opener.can_open
+                        && can_match(opener, closer, &items[index + 1..closer_index])
+                }
+                _ => false,
+            });
+
+        let opener_index = match opener_index {
+            Some(index) => index,
+            None => {
+                closer_index += 1;
+                continue;
+            }
+        };
+
+        let opener_count = match &items[opener_index] {
+            Item::Delimiter(opener) => opener.count,
+            _ => unreachable!(),
+        };
+        let used = if opener_count >= 2 && closer.count >= 2 {
+            2
+        } else {
+            1
+        };
+        let character = closer.character;
+
+        let content = items_to_line(items.drain(opener_index + 1..closer_index).collect());
+        let element = match (character, used) {
+            ('~', 2) => InlineElement::Strikethrough(content),
+            ('~', _) => InlineElement::Subscript(content),
+            ('=', _) => InlineElement::Highlight(content),
+            ('^', _) => InlineElement::Superscript(content),
+            _ => InlineElement::Emphasis(used as u32, content),
+        };
+        items.insert(opener_index + 1, Item::Element(element));
+        closer_index = opener_index + 2;
+
+        if use_delimiters(&mut items[closer_index], used) {
+            items.remove(closer_index);
+        }
+
+        if use_delimiters(&mut items[opener_index], used) {
+            items.remove(opener_index);
+            closer_index -= 1;
+        }
+    }
+}
+
+/// Removes `count` characters from a delimiter run, returning true if it is used up
+fn use_delimiters(item: &mut Item, count: usize) -> bool {
+    match item {
+        Item::Delimiter(delimiter) => {
+            delimiter.count -= count;
+            delimiter.count == 0
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn can_match(opener: &Delimiter, closer: &Delimiter, content: &[Item]) -> bool {
+    match opener.character {
+        '*' | '_' => !violates_rule_of_three(opener, closer),
+        // Superscripts and subscripts cannot contain spaces
+        '^' | '~' if opener.count == 1 => {
+            closer.count == 1
+                && !content.iter().any(|item| match item {
+                    Item::Text(text) => text.contains(char::is_whitespace),
+                    Item::Element(element) => {
+                        matches!(element, InlineElement::SoftBreak | InlineElement::HardBreak)
+                    }
+                    _ => false,
+                })
+        }
+        _ => opener.count == closer.count,
+    }
+}
+
+/// If either delimiter can both open and close, the sum of their lengths cannot be a multiple of 3
+/// unless both lengths are. This keeps `*foo**bar*` from being parsed as `*foo*` and `*bar*`.
+fn violates_rule_of_three(opener: &Delimiter, closer: &Delimiter) -> bool {
+    (opener.can_close || closer.can_open)
+        && (opener.original_count + closer.original_count).is_multiple_of(3)
+        && !(opener.original_count.is_multiple_of(3) && closer.original_count.is_multiple_of(3))
+}
+
+fn items_to_line(items: Vec<Item>) -> Line {
+    let mut line = Line::default();
+
+    let push_text = |line: &mut Line, s: &str| match line.elements.last_mut() {
+        Some(InlineElement::Text(text)) => text.push_str(s),
+        _ => line.elements.push(InlineElement::Text(s.to_string())),
+    };
+
+    for item in items {
+        match item {
+            Item::Text(text) => push_text(&mut line, &text),
+            Item::Bracket { image } => push_text(&mut line, if image { "![" } else { "[" }),
+            Item::Delimiter(delimiter) => push_text(
+                &mut line,
+                &delimiter.character.to_string().repeat(delimiter.count),
+            ),
+            Item::Element(element) => line.elements.push(element),
+        }
+    }
+
+    line
+}
+
+fn count_leading(s: &str, c: char) -> usize {
+    s.chars().take_while(|&x| x == c).count()
+}
+
+/// ASCII punctuation, or any non-ASCII character that is not alphanumeric or whitespace (which
+/// approximates the Unicode punctuation and symbol categories)
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || (!c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::document::{InlineElement, Line};
+    use crate::markdown::inline_parser::{link_bare_urls, parse_inlines};
+    use crate::markdown::references::LinkReferences;
+    use crate::util::html::Attributes;
+
+    fn parse(text: &str) -> Line {
+        parse_inlines(text, &LinkReferences::new())
+    }
+
+    fn text(s: &str) -> InlineElement {
+        InlineElement::Text(s.to_string())
+    }
+
+    fn emphasis(level: u32, elements: Vec<InlineElement>) -> InlineElement {
+        InlineElement::Emphasis(level, Line::new(elements))
+    }
+
+    #[test]
+    fn test_emphasis() {
+        assert_eq!(
+            parse("*italic* and **bold** and _italic_ and __bold__").elements,
+            vec![
+                emphasis(1, vec![text("italic")]),
+                text(" and "),
+                emphasis(2, vec![text("bold")]),
+                text(" and "),
+                emphasis(1, vec![text("italic")]),
+                text(" and "),
+                emphasis(2, vec![text("bold")]),
+            ]
+        );
+
+        assert_eq!(
+            parse("***bold italic***").elements,
+            vec![emphasis(1, vec![emphasis(2, vec![text("bold italic")])])]
+        );
+
+        assert_eq!(
+            parse("**bold *nested italic* bold**").elements,
+            vec![emphasis(
+                2,
+                vec![
+                    text("bold "),
+                    emphasis(1, vec![text("nested italic")]),
+                    text(" bold"),
+                ]
+            )]
+        );
+
+        // Delimiters are matched over all the lines of a paragraph
+        assert_eq!(
+            parse("*foo\nbar*").elements,
+            vec![emphasis(
+                1,
+                vec![text("foo"), InlineElement::SoftBreak, text("bar")]
+            )]
+        );
+
+        assert_eq!(
+            parse("**foo\n*bar\nbaz*\nqux**").elements,
+            vec![emphasis(
+                2,
+                vec![
+                    text("foo"),
+                    InlineElement::SoftBreak,
+                    emphasis(1, vec![text("bar"), InlineElement::SoftBreak, text("baz")]),
+                    InlineElement::SoftBreak,
+                    text("qux"),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_intraword_emphasis() {
+        assert_eq!(
+            parse("snake_case_name").elements,
+            vec![text("snake_case_name")]
+        );
+
+        assert_eq!(
+            parse("un*frigging*believable").elements,
+            vec![
+                text("un"),
+                emphasis(1, vec![text("frigging")]),
+                text("believable"),
+            ]
+        );
+
+        assert_eq!(
+            parse("Magna etiam _tempor orci__eu_ lobortis").elements,
+            vec![
+                text("Magna etiam "),
+                emphasis(1, vec![text("tempor orci__eu")]),
+                text(" lobortis"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_emphasis() {
+        assert_eq!(
+            parse("**unclosed bold").elements,
+            vec![text("**unclosed bold")]
+        );
+
+        assert_eq!(
+            parse("**one extra*").elements,
+            vec![text("*"), emphasis(1, vec![text("one extra")])]
+        );
+
+        assert_eq!(
+            parse("*foo**bar*").elements,
+            vec![emphasis(1, vec![text("foo**bar")])]
+        );
+
+        assert_eq!(
+            parse("a * not emphasis *").elements,
+            vec![text("a * not emphasis *")]
+        );
+    }
+
+    #[test]
+    fn test_links_and_emphasis() {
+        assert_eq!(
+            parse("*[link*](/url)").elements,
+            vec![
+                text("*"),
+                InlineElement::Link {
+                    text: Line::from_str("link*"),
+                    url: "/url".to_string(),
+                    title: None,
+                    attributes: Attributes::default(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse("[`code` link](/url)").elements,
+            vec![InlineElement::Link {
+                text: Line::new(vec![InlineElement::Code("code".to_string()), text(" link")]),
+                url: "/url".to_string(),
+                title: None,
+                attributes: Attributes::default(),
+            }]
+        );
+
+        assert_eq!(
+            parse("[outer [inner](/inner)](/outer)").elements,
+            vec![
+                text("[outer "),
+                InlineElement::Link {
+                    text: Line::from_str("inner"),
+                    url: "/inner".to_string(),
+                    title: None,
+                    attributes: Attributes::default(),
+                },
+                text("](/outer)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strikethrough_highlight_superscript_subscript() {
+        assert_eq!(
+            parse("~~removed~~ and ==marked== text").elements,
+            vec![
+                InlineElement::Strikethrough(Line::from_str("removed")),
+                text(" and "),
+                InlineElement::Highlight(Line::from_str("marked")),
+                text(" text"),
+            ]
+        );
+
+        assert_eq!(
+            parse("H~2~O and x^2^").elements,
+            vec![
+                text("H"),
+                InlineElement::Subscript(Line::from_str("2")),
+                text("O and x"),
+                InlineElement::Superscript(Line::from_str("2")),
+            ]
+        );
+
+        assert_eq!(
+            parse("~~*struck emphasis*~~").elements,
+            vec![InlineElement::Strikethrough(Line::new(vec![emphasis(
+                1,
+                vec![text("struck emphasis")]
+            )]))]
+        );
+
+        assert_eq!(
+            parse("~not a subscript~ and ~~mismatched~ and a == b").elements,
+            vec![text("~not a subscript~ and ~~mismatched~ and a == b")]
+        );
+
+        assert_eq!(
+            parse("~~~too many~~~ and =single= and ^^double^^").elements,
+            vec![text("~~~too many~~~ and =single= and ^^double^^")]
+        );
+    }
+
+    #[test]
+    fn test_autolinks() {
+        let link = |text: &str, url: &str| InlineElement::Link {
+            text: Line::from_str(text),
+            url: url.to_string(),
+            title: None,
+            attributes: Attributes::default(),
+        };
+
+        assert_eq!(
+            parse("<https://example.com/a?b=c> and <me@example.com>").elements,
+            vec![
+                link("https://example.com/a?b=c", "https://example.com/a?b=c"),
+                text(" and "),
+                link("me@example.com", "mailto:me@example.com"),
+            ]
+        );
+
+        assert_eq!(
+            parse("< not an autolink> and <a@b").elements,
+            vec![text("< not an autolink> and <a@b")]
+        );
+    }
+
+    #[test]
+    fn test_link_bare_urls() {
+        let link = |text: &str, url: &str| InlineElement::Link {
+            text: Line::from_str(text),
+            url: url.to_string(),
+            title: None,
+            attributes: Attributes::default(),
+        };
+        let link_bare_urls = |s: &str| {
+            let mut line = parse(s);
+            link_bare_urls(&mut line);
+            line.elements
+        };
+
+        assert_eq!(
+            link_bare_urls("Visit https://example.com/docs, www.example.com or me@example.com."),
+            vec![
+                text("Visit "),
+                link("https://example.com/docs", "https://example.com/docs"),
+                text(", "),
+                link("www.example.com", "http://www.example.com"),
+                text(" or "),
+                link("me@example.com", "mailto:me@example.com"),
+                text("."),
+            ]
+        );
+
+        assert_eq!(
+            link_bare_urls("(see https://en.wikipedia.org/wiki/Rust_(language))"),
+            vec![
+                text("(see "),
+                link(
+                    "https://en.wikipedia.org/wiki/Rust_(language)",
+                    "https://en.wikipedia.org/wiki/Rust_(language)"
+                ),
+                text(")"),
+            ]
+        );
+
+        assert_eq!(
+            link_bare_urls("*https://example.com* and `https://example.com`"),
+            vec![
+                emphasis(1, vec![link("https://example.com", "https://example.com")]),
+                text(" and "),
+                InlineElement::Code("https://example.com".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            link_bare_urls("[https://example.com](/other) and https:// alone"),
+            vec![
+                InlineElement::Link {
+                    text: Line::from_str("https://example.com"),
+                    url: "/other".to_string(),
+                    title: None,
+                    attributes: Attributes::default(),
+                },
+                text(" and https:// alone"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_math() {
+        let math = |source: &str, display: bool| InlineElement::Math {
+            source: source.to_string(),
+            display,
+        };
+
+        assert_eq!(
+            parse("If $x^2 = 4$ then $$x = \\pm 2$$").elements,
+            vec![
+                text("If "),
+                math("x^2 = 4", false),
+                text(" then "),
+                math("x = \\pm 2", true),
+            ]
+        );
+
+        // Prices, escaped dollars and math in code are left alone
+        assert_eq!(
+            parse("From $5 to $10").elements,
+            vec![text("From $5 to $10")]
+        );
+        assert_eq!(
+            parse("`$y$`").elements,
+            vec![InlineElement::Code("$y$".to_string())]
+        );
+        assert_eq!(parse("\\$x$").elements, vec![text("$x$")]);
+        assert_eq!(parse("$ x$ and $a$1").elements, vec![text("$ x$ and $a$1")]);
+        assert_eq!(
+            parse("Price is $5 and $x^2$.").elements,
+            vec![text("Price is $5 and "), math("x^2", false), text(".")]
+        );
+        assert_eq!(
+            parse("$a $b$ and $c\\\\$").elements,
+            vec![math("a $b", false), text(" and "), math("c\\\\", false)]
+        );
+        assert_eq!(
+            parse("$\\$$ and *$a*b$*").elements,
+            vec![
+                math("\\$", false),
+                text(" and "),
+                emphasis(1, vec![math("a*b", false)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_html() {
+        let html = |html: &str| InlineElement::Html(html.to_string());
+
+        assert_eq!(
+            parse("<span class=\"note\" hidden>*a*</span> <br/>").elements,
+            vec![
+                html("<span class=\"note\" hidden>"),
+                emphasis(1, vec![text("a")]),
+                html("</span>"),
+                text(" "),
+                html("<br/>"),
+            ]
+        );
+
+        assert_eq!(
+            parse("<!-- comment --> <?php echo 1; ?> <!DOCTYPE html> <![CDATA[x]]>").elements,
+            vec![
+                html("<!-- comment -->"),
+                text(" "),
+                html("<?php echo 1; ?>"),
+                text(" "),
+                html("<!DOCTYPE html>"),
+                text(" "),
+                html("<![CDATA[x]]>"),
+            ]
+        );
+
+        assert_eq!(
+            parse("<a href='x'y> <33> <!-- a -- b --> `<b>`").elements,
+            vec![
+                text("<a href='x'y> <33> <!-- a -- b --> "),
+                InlineElement::Code("<b>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_attributes() {
+        let attributes = Attributes {
+            id: Some("logo".to_string()),
+            classes: vec!["small".to_string()],
+            pairs: vec![("width".to_string(), "64".to_string())],
+        };
+
+        assert_eq!(
+            parse("![Logo](logo.png){#logo .small width=64} [a](/a){.x y}").elements,
+            vec![
+                InlineElement::Image {
+                    alt: Line::from_str("Logo"),
+                    url: "logo.png".to_string(),
+                    title: None,
+                    attributes,
+                },
+                text(" "),
+                InlineElement::Link {
+                    text: Line::from_str("a"),
+                    url: "/a".to_string(),
+                    title: None,
+                    attributes: Attributes::default(),
+                },
+                text("{.x y}"),
+            ]
+        );
+    }
+}
@@ -1,3 +1,12 @@
+mod attributes;
 mod builder;
 pub mod document;
+mod emoji;
+mod footnotes;
+mod headers;
+mod inline_parser;
 pub mod parser;
+mod references;
+#[cfg(test)]
+mod spec_test;
+mod typography;
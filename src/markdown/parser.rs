@@ -1,17 +1,18 @@
-use crate::markdown::builder::ParagraphBuilder;
-use crate::markdown::document::{Document, Element, InlineElement, Line};
-use crate::util::regex_split::split_by_regex;
+use crate::markdown::attributes::strip_trailing_attributes;
+use crate::markdown::builder::{ContainerBuilder, ListBuilder, ParagraphBuilder};
+use crate::markdown::document::{Alignment, DefinitionItem, Document, Element, Line, ListItem};
+use crate::markdown::emoji::expand_emoji_shortcodes;
+use crate::markdown::footnotes::resolve_footnotes;
+use crate::markdown::headers::assign_header_ids;
+use crate::markdown::inline_parser::{
+    link_bare_urls, parse_inlines, HTML_CLOSING_TAG_PATTERN, HTML_OPEN_TAG_PATTERN,
+};
+use crate::markdown::references::{take_link_definitions, LinkReferences};
+use crate::markdown::typography::apply_smart_punctuation;
+use crate::util::html::Attributes;
 use fancy_regex::Regex;
 use lazy_static::lazy_static;
-
-macro_rules! return_if_some {
-    ($a:expr) => {
-        match $a {
-            Some(x) => return x,
-            None => {}
-        }
-    };
-}
+use std::mem;
 
 macro_rules! return_option_if_some {
     ($a:expr) => {
@@ -22,14 +23,146 @@ macro_rules! return_option_if_some {
     };
 }
 
+#[derive(Debug, Default)]
+pub struct ParseOptions {
+    /// Turns bare URLs (`https://...` or `www....`) and email addresses into links
+    pub bare_urls: bool,
+    /// Replaces straight quotes, dashes and ellipses in text with typographic ones
+    pub smart_punctuation: bool,
+    /// The language of the text, such as `en` or `fr`, which affects smart punctuation. The same
+    /// options are used for every page of a site, so pages cannot override it.
+    pub language: String,
+}
+
+#[cfg(test)]
 pub fn parse(raw_text: &str) -> Document {
+    parse_with_options(raw_text, &ParseOptions::default())
+}
+
+pub fn parse_with_options(raw_text: &str, options: &ParseOptions) -> Document {
+    let lines: Vec<&str> = raw_text.lines().collect();
+
+    // Blocks are parsed first, leaving their inline content as raw text. This lets link reference
+    // definitions be collected from the whole document before any inline content is parsed.
+    let mut elements = parse_blocks(&lines, 1).0;
+
+    let mut references = LinkReferences::new();
+    take_link_definitions(&mut elements, &mut references);
+
+    for element in &mut elements {
+        // Inlines such as emphasis and links can span the lines of a paragraph, so they are parsed
+        // from all of its lines at once
+        element.for_each_block_mut(&mut |block| {
+            if let Element::Paragraph(lines, _) = block {
+                let text = lines
+                    .iter()
+                    .map(Line::to_plain_text)
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                *lines = vec![Line::from_str_at(&text, lines[0].source_line)];
+            }
+        });
+
+        element.for_each_line_mut(&mut |line| {
+            let source_line = line.source_line;
+            *line = parse_line(&line.to_plain_text(), &references);
+            line.source_line = source_line;
+
+            if options.bare_urls {
+                link_bare_urls(line);
+            }
+        });
+    }
+
+    let mut document = Document {
+        elements,
+        footnotes: vec![],
+    };
+    resolve_footnotes(&mut document);
+    assign_header_ids(&mut document);
+    expand_emoji_shortcodes(&mut document);
+
+    // Header ids are made from the original text, so that they don't depend on this option
+    if options.smart_punctuation {
+        apply_smart_punctuation(&mut document, &options.language);
+    }
+
+    return document;
+}
+
+/// Parses a sequence of lines into block elements, where `first_line` is the line of the Markdown
+/// text that the first of them comes from. The returned flag is true if any two of the blocks are
+/// separated by a blank line, which is used to decide whether a list is loose.
+fn parse_blocks(lines: &[&str], first_line: usize) -> (Vec<Element>, bool) {
     let mut elements = vec![];
     let mut paragraph_builder = ParagraphBuilder::default();
 
-    for line in raw_text.lines() {
-        match parse_raw_line(line) {
+    let mut blank_line_pending = false;
+    let mut blank_line_between_blocks = false;
+
+    let mut index = 0;
+    while index < lines.len() {
+        if lines[index].trim().is_empty() {
+            paragraph_builder.finish(&mut elements);
+            blank_line_pending = !elements.is_empty();
+            index += 1;
+            continue;
+        }
+
+        if blank_line_pending {
+            blank_line_between_blocks = true;
+            blank_line_pending = false;
+        }
+
+        if !paragraph_builder.is_empty() {
+            if let Some(level) = parse_setext_underline(lines[index]) {
+                paragraph_builder.finish_as_header(level, &mut elements);
+                index += 1;
+                continue;
+            }
+        }
+
+        // A definition turns the paragraph before it into the terms of a definition list, which is
+        // loose if they are separated by a blank line
+        if parse_definition_marker(lines[index]).is_some() {
+            let terms = if !paragraph_builder.is_empty() {
+                Some((paragraph_builder.take_lines(), false))
+            } else if index > 0 && lines[index - 1].trim().is_empty() {
+                match elements.pop() {
+                    Some(Element::Paragraph(lines, _)) => Some((lines, true)),
+                    Some(element) => {
+                        elements.push(element);
+                        None
+                    }
+                    None => None,
+                }
+            } else {
+                None
+            };
+
+            if let Some((terms, loose)) = terms {
+                let (element, line_count) =
+                    parse_definition_list(terms, loose, &lines[index..], first_line + index);
+                elements.push(element);
+                index += line_count;
+                continue;
+            }
+        }
+
+        if let Some((element, line_count)) = parse_container_block(
+            &lines[index..],
+            !paragraph_builder.is_empty(),
+            first_line + index,
+        ) {
+            paragraph_builder.finish(&mut elements);
+            elements.push(element);
+            index += line_count;
+            continue;
+        }
+
+        match parse_raw_line(lines[index], first_line + index) {
             Some(element) => match element {
-                Element::Paragraph(lines) => {
+                Element::Paragraph(lines, _) => {
                     paragraph_builder.add_lines(lines);
                 }
                 _ => {
@@ -41,24 +174,72 @@ pub fn parse(raw_text: &str) -> Document {
                 paragraph_builder.finish(&mut elements);
             }
         };
+
+        index += 1;
     }
 
     paragraph_builder.finish(&mut elements);
 
-    return Document { elements };
+    (elements, blank_line_between_blocks)
+}
+
+/// Parses a block that can span multiple lines, returning it along with the number of lines
+/// consumed. `in_paragraph` is true if the block would interrupt a paragraph, and `first_line` is
+/// the line of the Markdown text that the block starts on.
+fn parse_container_block(
+    lines: &[&str],
+    in_paragraph: bool,
+    first_line: usize,
+) -> Option<(Element, usize)> {
+    if is_thematic_break(lines.first()?) {
+        return Some((Element::ThematicBreak, 1));
+    }
+
+    return_option_if_some!(parse_fenced_code_block(lines, first_line));
+    return_option_if_some!(parse_fenced_admonition(lines, first_line));
+    return_option_if_some!(parse_math_block(lines, first_line));
+    return_option_if_some!(parse_html_block(lines, in_paragraph));
+
+    // Indented code blocks and footnote definitions cannot interrupt a paragraph
+    if !in_paragraph {
+        return_option_if_some!(parse_indented_code_block(lines));
+        return_option_if_some!(parse_footnote_definition(lines, first_line));
+    }
+
+    return_option_if_some!(parse_block_quote(lines, first_line));
+    return_option_if_some!(parse_table(lines, first_line));
+    return_option_if_some!(parse_list(lines, in_paragraph, first_line));
+
+    None
+}
+
+/// Returns true if the line would start a new block rather than continue a paragraph
+fn starts_block(line: &str) -> bool {
+    parse_header(line, 0).is_some()
+        || is_thematic_break(line)
+        || parse_fenced_code_block(&[line], 0).is_some()
+        || parse_fenced_admonition(&[line], 0).is_some()
+        || parse_math_block(&[line], 0).is_some()
+        || parse_html_block(&[line], true).is_some()
+        || strip_block_quote_marker(line).is_some()
+        || parse_list_marker(line).is_some_and(|marker| !marker.content.trim().is_empty())
 }
 
-fn parse_raw_line(line: &str) -> Option<Element> {
+fn parse_raw_line(line: &str, source_line: usize) -> Option<Element> {
     if line.trim().len() == 0 {
         return None;
     }
 
-    return_option_if_some!(parse_header(line));
+    return_option_if_some!(parse_header(line, source_line));
 
-    return Some(Element::Paragraph(vec![parse_line(line)]));
+    // Trailing whitespace is kept until the paragraph is finished, since it may be a hard break
+    return Some(Element::Paragraph(
+        vec![Line::from_str_at(line.trim_start(), source_line)],
+        Attributes::default(),
+    ));
 }
 
-fn parse_header(line: &str) -> Option<Element> {
+fn parse_header(line: &str, source_line: usize) -> Option<Element> {
     lazy_static! {
         static ref HEADER_PATTERN: Regex = Regex::new(r"^(#+)\s+(.*)$").unwrap();
     }
@@ -68,118 +249,2071 @@ fn parse_header(line: &str) -> Option<Element> {
         Err(_) => return None,
     }?;
 
-    return Some(Element::Header(
-        caps.get(1).unwrap().as_str().len() as u32,
-        parse_line(caps.get(2).unwrap().as_str()),
-    ));
+    let (text, attributes) = strip_trailing_attributes(caps.get(2).unwrap().as_str());
+    let mut attributes = attributes.unwrap_or_default();
+
+    return Some(Element::Header {
+        level: caps.get(1).unwrap().as_str().len() as u32,
+        id: attributes.id.take().unwrap_or_default(),
+        line: Line::from_str_at(text, source_line),
+        attributes,
+    });
 }
 
-fn parse_line(line: &str) -> Line {
-    if line.trim().len() == 0 {
-        return Line::from_str("");
+/// Parses a setext header underline (`===` or `---`), returning the header level
+fn parse_setext_underline(line: &str) -> Option<u32> {
+    lazy_static! {
+        static ref SETEXT_UNDERLINE_PATTERN: Regex = Regex::new(r"^ {0,3}(=+|-+)[ \t]*$").unwrap();
     }
 
-    return_if_some!(parse_emphasis(line));
+    let caps = match SETEXT_UNDERLINE_PATTERN.captures(line) {
+        Ok(c) => c,
+        Err(_) => return None,
+    }?;
 
-    return Line::from_str(line);
+    if caps.get(1).unwrap().as_str().starts_with('=') {
+        Some(1)
+    } else {
+        Some(2)
+    }
 }
 
-fn parse_emphasis(line: &str) -> Option<Line> {
+fn is_thematic_break(line: &str) -> bool {
     lazy_static! {
-        static ref EMPHASIS_PATTERN: Regex = Regex::new(r"((?:\*|_){1,2})(.+?)\1").unwrap();
+        static ref THEMATIC_BREAK_PATTERN: Regex =
+            Regex::new(r"^ {0,3}(?:(?:\*[ \t]*){3,}|(?:-[ \t]*){3,}|(?:_[ \t]*){3,})$").unwrap();
     }
 
-    match EMPHASIS_PATTERN.is_match(line) {
-        Ok(b) => {
-            if !b {
-                return None;
-            }
-        }
+    THEMATIC_BREAK_PATTERN.is_match(line).unwrap_or(false)
+}
+
+/// Parses a fenced code block starting at the first line, returning it along with the number of
+/// lines consumed. An unclosed fence runs to the end of the document.
+fn parse_fenced_code_block(lines: &[&str], first_line: usize) -> Option<(Element, usize)> {
+    lazy_static! {
+        static ref FENCE_PATTERN: Regex =
+            Regex::new(r"^( {0,3})(`{3,}(?=[^`]*$)|~{3,})\s*(.*?)\s*$").unwrap();
+    }
+
+    let caps = match FENCE_PATTERN.captures(lines.first()?) {
+        Ok(c) => c,
         Err(_) => return None,
+    }?;
+
+    let indent = caps.get(1).unwrap().as_str().len();
+    let fence = caps.get(2).unwrap().as_str();
+    let (info, attributes) = strip_trailing_attributes(caps.get(3).unwrap().as_str());
+    let info = info.to_string();
+    let attributes = attributes.unwrap_or_default();
+
+    let mut code_lines = vec![];
+    let mut line_count = lines.len();
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if is_closing_fence(line, fence) {
+            line_count = index + 1;
+            break;
+        }
+
+        code_lines.push(remove_indentation(line, indent));
     }
 
-    let elements = split_by_regex(
-        line,
-        &*EMPHASIS_PATTERN,
-        |captures| {
-            InlineElement::Emphasis(
-                captures.get(1).unwrap().as_str().len() as u32,
-                parse_line(captures.get(2).unwrap().as_str()),
-            )
+    // A `math` fence holds display math rather than code
+    if info.split_whitespace().next() == Some("math") {
+        let math = Element::MathBlock {
+            source: code_lines.join("\n"),
+            source_line: first_line + 1,
+        };
+        return Some((math, line_count));
+    }
+
+    Some((
+        Element::CodeBlock {
+            info,
+            lines: code_lines,
+            attributes,
         },
-        |text| InlineElement::Text(text.to_string()), // TODO
-    );
+        line_count,
+    ))
+}
+
+/// Parses display math between `$$` delimiters at the start and end of its lines, which may also
+/// contain the math itself. Display math cannot contain blank lines.
+fn parse_math_block(lines: &[&str], source_line: usize) -> Option<(Element, usize)> {
+    let first_line = lines.first()?;
+    if indentation_width(first_line) > 3 {
+        return None;
+    }
+
+    let opening = first_line.trim().strip_prefix("$$")?;
+    if let Some(source) = opening.strip_suffix("$$") {
+        if source.contains("$$") || source.trim().is_empty() {
+            return None;
+        }
+
+        let math = Element::MathBlock {
+            source: source.trim().to_string(),
+            source_line,
+        };
+        return Some((math, 1));
+    }
+
+    if opening.contains("$$") {
+        return None;
+    }
+
+    let mut source_lines = vec![opening];
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        if let Some(last_line) = line.trim().strip_suffix("$$") {
+            source_lines.push(last_line);
+
+            let source = source_lines
+                .iter()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<&str>>()
+                .join("\n");
+            // The math starts after the opening `$$` if that is on a line of its own
+            let math = Element::MathBlock {
+                source,
+                source_line: source_line + usize::from(opening.trim().is_empty()),
+            };
+            return Some((math, index + 1));
+        }
+
+        source_lines.push(line);
+    }
 
-    return Some(Line { elements });
+    None
 }
 
-#[cfg(test)]
-mod test {
-    use crate::markdown::document::{Document, Element, InlineElement, Line};
-    use crate::markdown::parser::{parse, parse_header, parse_line};
-    use std::fs;
+fn is_closing_fence(line: &str, opening_fence: &str) -> bool {
+    let fence_char = opening_fence.chars().next().unwrap();
+    let trimmed = line.trim_end();
+    let fence = trimmed.trim_start_matches(' ');
 
-    #[test]
-    fn test_parse_with_headings_paragraphs() {
-        let raw_text = fs::read_to_string("test_data/markdown/headings_paragraphs.md").unwrap();
-        let parsed_doc = parse(&raw_text);
+    trimmed.len() - fence.len() <= 3
+        && fence.len() >= opening_fence.len()
+        && fence.chars().all(|c| c == fence_char)
+}
 
-        assert_eq!(
-            parsed_doc,
-            Document {
-                elements: vec![
-                    Element::Header(1, Line::from_str("Test of my markdown parser")),
-                    Element::Paragraph(vec![
-                        Line::from_str("This is a test file for my markdown parser."),
-                        Line::from_str("This file only contains headings and paragraphs.")
-                    ]),
-                    Element::Header(1, Line::from_str("This is a second header")),
-                    Element::Paragraph(vec![Line::from_str("And more text under the header.")]),
-                    Element::Paragraph(vec![Line::from_str(
-                        "This is a separate paragraph under the same heading."
-                    )]),
-                    Element::Header(2, Line::from_str("Here is a sub-header")),
-                    Element::Paragraph(vec![
-                        Line::from_str("Random text here."),
-                        Line::from_str("A second line of random text."),
-                        Line::from_str("A third line of random text."),
-                    ]),
-                ]
+/// Parses an HTML block starting at the first line using CommonMark's start and end conditions,
+/// returning it along with the number of lines consumed. An unclosed block runs to the end of the
+/// document.
+fn parse_html_block(lines: &[&str], in_paragraph: bool) -> Option<(Element, usize)> {
+    lazy_static! {
+        // The end condition of each kind of block, where `None` means it ends before a blank line
+        static ref HTML_BLOCK_CONDITIONS: Vec<(Regex, Option<Regex>)> = vec![
+            (
+                Regex::new(r"(?i)^ {0,3}<(?:script|pre|style|textarea)(?:[\s>]|$)").unwrap(),
+                Some(Regex::new(r"(?i)</(?:script|pre|style|textarea)>").unwrap()),
+            ),
+            (
+                Regex::new(r"^ {0,3}<!--").unwrap(),
+                Some(Regex::new(r"-->").unwrap()),
+            ),
+            (
+                Regex::new(r"^ {0,3}<\?").unwrap(),
+                Some(Regex::new(r"\?>").unwrap()),
+            ),
+            (
+                Regex::new(r"^ {0,3}<![A-Za-z]").unwrap(),
+                Some(Regex::new(r">").unwrap()),
+            ),
+            (
+                Regex::new(r"^ {0,3}<!\[CDATA\[").unwrap(),
+                Some(Regex::new(r"\]\]>").unwrap()),
+            ),
+            (
+                Regex::new(
+                    r"(?i)^ {0,3}</?(?:address|article|aside|base|basefont|blockquote|body|caption|center|col|colgroup|dd|details|dialog|dir|div|dl|dt|fieldset|figcaption|figure|footer|form|frame|frameset|h[1-6]|head|header|hr|html|iframe|legend|li|link|main|menu|menuitem|nav|noframes|ol|optgroup|option|p|param|section|source|summary|table|tbody|td|tfoot|th|thead|title|tr|track|ul)(?:\s|/?>|$)"
+                )
+                .unwrap(),
+                None,
+            ),
+        ];
+        // Any other complete tag on a line of its own, which cannot interrupt a paragraph
+        static ref HTML_TAG_LINE_PATTERN: Regex = Regex::new(&format!(
+            r"^ {{0,3}}(?!</?(?i:script|pre|style|textarea)(?![A-Za-z0-9-]))(?:{}|{})\s*$",
+            HTML_OPEN_TAG_PATTERN, HTML_CLOSING_TAG_PATTERN
+        ))
+        .unwrap();
+    }
+
+    let first_line = lines.first()?;
+    let end_condition = match HTML_BLOCK_CONDITIONS
+        .iter()
+        .find(|(start, _)| start.is_match(first_line).unwrap_or(false))
+    {
+        Some((_, end)) => end.as_ref(),
+        None if !in_paragraph && HTML_TAG_LINE_PATTERN.is_match(first_line).unwrap_or(false) => {
+            None
+        }
+        None => return None,
+    };
+
+    let line_count = match end_condition {
+        Some(end) => lines
+            .iter()
+            .position(|line| end.is_match(line).unwrap_or(false))
+            .map_or(lines.len(), |index| index + 1),
+        None => lines
+            .iter()
+            .position(|line| line.trim().is_empty())
+            .unwrap_or(lines.len()),
+    };
+
+    Some((
+        Element::HtmlBlock(
+            lines[..line_count]
+                .iter()
+                .map(|line| line.to_string())
+                .collect(),
+        ),
+        line_count,
+    ))
+}
+
+/// Parses an indented code block starting at the first line, returning it along with the number
+/// of lines consumed. Trailing blank lines are not part of the block.
+fn parse_indented_code_block(lines: &[&str]) -> Option<(Element, usize)> {
+    let mut code_lines = vec![];
+    let mut line_count = 0;
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            code_lines.push(remove_indentation(line, 4));
+            continue;
+        }
+
+        if indentation_width(line) < 4 {
+            break;
+        }
+
+        code_lines.push(remove_indentation(line, 4));
+        line_count = index + 1;
+    }
+
+    if line_count == 0 {
+        return None;
+    }
+
+    code_lines.truncate(line_count);
+
+    Some((
+        Element::CodeBlock {
+            info: String::new(),
+            lines: code_lines,
+            attributes: Attributes::default(),
+        },
+        line_count,
+    ))
+}
+
+/// Removes the `>` marker from a block quote line, along with a single space after it
+fn strip_block_quote_marker(line: &str) -> Option<String> {
+    let indent = indentation_width(line);
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = line.trim_start().strip_prefix('>')?;
+
+    Some(match rest.strip_prefix(' ') {
+        Some(rest) => rest.to_string(),
+        None => match rest.strip_prefix('\t') {
+            // The tab only counts as a single space, so the rest of its width is kept
+            Some(rest) => " ".repeat(3 - (indent + 1) % 4) + rest,
+            None => rest.to_string(),
+        },
+    })
+}
+
+/// Parses a block quote starting at the first line, returning it along with the number of lines
+/// consumed. Each line either starts with `>` or lazily continues a paragraph inside the quote.
+fn parse_block_quote(lines: &[&str], first_line: usize) -> Option<(Element, usize)> {
+    let mut quote_builder = ContainerBuilder::default();
+    quote_builder.add_line(strip_block_quote_marker(lines.first()?)?);
+
+    let mut line_count = 1;
+    for line in lines.iter().skip(1) {
+        match strip_block_quote_marker(line) {
+            Some(stripped) => quote_builder.add_line(stripped),
+            None if !line.trim().is_empty()
+                && !quote_builder.last_line_is_blank()
+                && !starts_block(line) =>
+            {
+                quote_builder.add_line(line.to_string())
             }
+            None => break,
+        }
+
+        line_count += 1;
+    }
+
+    let quote_lines = quote_builder.finish();
+
+    // A GitHub style alert (`> [!NOTE]`), which may be followed by a title
+    if let Some((kind, title)) = quote_lines
+        .first()
+        .and_then(|line| parse_admonition_marker(line))
+    {
+        let (body, _) = parse_blocks(
+            &quote_lines[1..]
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<&str>>(),
+            first_line + 1,
         );
+
+        return Some((admonition(kind, title, first_line, body), line_count));
     }
 
-    #[test]
-    fn test_parse_header() {
-        let gen_header_prefix = |level: u32| String::from("#").repeat(level as usize);
-        assert_eq!(gen_header_prefix(1), "#");
-        assert_eq!(gen_header_prefix(2), "##");
-        assert_eq!(gen_header_prefix(3), "###");
+    let (elements, _) = parse_blocks(
+        &quote_lines
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>(),
+        first_line,
+    );
 
-        let assert_header = |s: &str| {
-            for level in 1..10 {
-                let raw_text = format!("{} {}", gen_header_prefix(level), s);
-                assert_eq!(
-                    parse_header(&raw_text),
-                    Some(Element::Header(level, Line::from_str(s)))
-                );
+    Some((Element::BlockQuote(elements), line_count))
+}
+
+/// Parses the `[!KIND]` marker on the first line of an admonition written as a block quote,
+/// returning the kind along with the rest of the line
+fn parse_admonition_marker(line: &str) -> Option<(String, String)> {
+    lazy_static! {
+        static ref ADMONITION_MARKER_PATTERN: Regex =
+            Regex::new(r"^[ \t]*\[!([A-Za-z]+)\][ \t]*(.*?)[ \t]*$").unwrap();
+    }
+
+    let caps = ADMONITION_MARKER_PATTERN.captures(line).ok()??;
+
+    Some((
+        caps.get(1).unwrap().as_str().to_string(),
+        caps.get(2).unwrap().as_str().to_string(),
+    ))
+}
+
+/// Parses an admonition written as a fenced container (`:::kind Title`), which ends at a line of
+/// three or more colons. Containers may be nested, and run to the end of the document if they
+/// aren't closed.
+fn parse_fenced_admonition(lines: &[&str], first_line: usize) -> Option<(Element, usize)> {
+    lazy_static! {
+        static ref ADMONITION_FENCE_PATTERN: Regex =
+            Regex::new(r"^ {0,3}:{3,}[ \t]*([A-Za-z][\w-]*)[ \t]*(.*?)[ \t]*$").unwrap();
+        static ref CLOSING_ADMONITION_FENCE_PATTERN: Regex =
+            Regex::new(r"^ {0,3}:{3,}[ \t]*$").unwrap();
+    }
+
+    let caps = ADMONITION_FENCE_PATTERN.captures(lines.first()?).ok()??;
+    let kind = caps.get(1).unwrap().as_str().to_string();
+    let title = caps.get(2).unwrap().as_str().to_string();
+
+    let mut depth = 1;
+    let mut body_line_count = lines.len() - 1;
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if ADMONITION_FENCE_PATTERN.is_match(line).unwrap_or(false) {
+            depth += 1;
+        } else if CLOSING_ADMONITION_FENCE_PATTERN
+            .is_match(line)
+            .unwrap_or(false)
+        {
+            depth -= 1;
+            if depth == 0 {
+                body_line_count = index - 1;
+                break;
             }
-        };
+        }
+    }
 
-        assert_eq!(parse_header(""), None);
-        assert_eq!(parse_header("A test sentence"), None);
+    let (body, _) = parse_blocks(&lines[1..=body_line_count], first_line + 1);
+    let line_count = (body_line_count + 2).min(lines.len());
 
-        assert_header("");
-        assert_header("A test sentence");
+    Some((admonition(kind, title, first_line, body), line_count))
+}
+
+fn admonition(kind: String, title: String, title_line: usize, body: Vec<Element>) -> Element {
+    let kind = kind.to_lowercase();
+
+    // Without a title, the kind is used with its first letter capitalized
+    let title = if title.is_empty() {
+        let mut chars = kind.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    } else {
+        title
+    };
+
+    Element::Admonition {
+        kind,
+        title: Line::from_str_at(&title, title_line),
+        body,
     }
+}
 
-    #[test]
-    fn test_parse_line() {
-        let assert_line = |s: &str| {
-            assert_eq!(parse_line(s), Line::from_str(s));
-        };
+/// Parses a GitHub-style pipe table starting at the first line, returning it along with the number
+/// of lines consumed. The table ends at the first blank line or line that starts another block.
+fn parse_table(lines: &[&str], first_line: usize) -> Option<(Element, usize)> {
+    if lines.len() < 2 || indentation_width(lines[0]) > 3 {
+        return None;
+    }
 
-        assert_line("");
-        assert_line("A test sentence");
+    let alignments = parse_table_delimiter_row(lines[1])?;
+    let header = split_table_row(lines[0]);
+    if header.len() != alignments.len() {
+        return None;
+    }
+
+    let parse_row = |line: &str, source_line: usize| {
+        let mut cells = split_table_row(line);
+        cells.resize(alignments.len(), String::new());
+        cells
+            .iter()
+            .map(|cell| Line::from_str_at(cell, source_line))
+            .collect()
+    };
+
+    let mut rows = vec![];
+    for (index, line) in lines.iter().enumerate().skip(2) {
+        if line.trim().is_empty() || starts_block(line) {
+            break;
+        }
+
+        rows.push(parse_row(line, first_line + index));
+    }
+
+    let line_count = rows.len() + 2;
+
+    Some((
+        Element::Table {
+            header: parse_row(lines[0], first_line),
+            alignments,
+            rows,
+        },
+        line_count,
+    ))
+}
+
+/// Parses the row separating a table's header from its body (e.g. `| :--- | :-: | --: |`)
+fn parse_table_delimiter_row(line: &str) -> Option<Vec<Alignment>> {
+    lazy_static! {
+        static ref DELIMITER_ROW_PATTERN: Regex =
+            Regex::new(r"^ {0,3}\|?(?:\s*:?-+:?\s*\|)*\s*:?-+:?\s*\|?\s*$").unwrap();
+    }
+
+    // Without a pipe, this would be a setext header underline or a thematic break
+    if !line.contains('|') || !DELIMITER_ROW_PATTERN.is_match(line).unwrap_or(false) {
+        return None;
+    }
+
+    Some(
+        split_table_row(line)
+            .iter()
+            .map(|cell| match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Alignment::Center,
+                (true, false) => Alignment::Left,
+                (false, true) => Alignment::Right,
+                (false, false) => Alignment::None,
+            })
+            .collect(),
+    )
+}
+
+/// Splits a table row into trimmed cells on unescaped pipes, ignoring leading and trailing pipes
+fn split_table_row(line: &str) -> Vec<String> {
+    let mut row = line.trim();
+    row = row.strip_prefix('|').unwrap_or(row);
+    if row.ends_with('|') && !row.ends_with("\\|") {
+        row = &row[..row.len() - 1];
+    }
+
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+
+    cells
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct ListMarker {
+    ordered: bool,
+    start: u32,
+    /// The bullet character, or the delimiter after the number of an ordered list item
+    delimiter: char,
+    /// The column that the item's content starts at
+    content_offset: usize,
+    /// The content of the first line of the item
+    content: String,
+}
+
+impl ListMarker {
+    fn continues_list(&self, other: &ListMarker) -> bool {
+        self.ordered == other.ordered && self.delimiter == other.delimiter
+    }
+}
+
+fn parse_list_marker(line: &str) -> Option<ListMarker> {
+    lazy_static! {
+        static ref LIST_MARKER_PATTERN: Regex =
+            Regex::new(r"^( {0,3})(?:([-+*])|(\d{1,9})([.)]))(?=[ \t]|$)").unwrap();
+    }
+
+    let caps = match LIST_MARKER_PATTERN.captures(line) {
+        Ok(c) => c,
+        Err(_) => return None,
+    }?;
+
+    let (ordered, start, delimiter) = match caps.get(2) {
+        Some(bullet) => (false, 1, bullet.as_str().chars().next().unwrap()),
+        None => (
+            true,
+            caps.get(3).unwrap().as_str().parse().unwrap(),
+            caps.get(4).unwrap().as_str().chars().next().unwrap(),
+        ),
+    };
+
+    let marker_width = caps.get(0).unwrap().end();
+
+    // Count the whitespace after the marker, taking into account which column the tabs start in
+    let rest = &line[marker_width..];
+    let mut spacing = 0;
+    let mut content_start = rest.len();
+    for (index, c) in rest.char_indices() {
+        match c {
+            ' ' => spacing += 1,
+            '\t' => spacing += 4 - (marker_width + spacing) % 4,
+            _ => {
+                content_start = index;
+                break;
+            }
+        }
+    }
+
+    let content = &rest[content_start..];
+    if content.is_empty() {
+        // An item starting with a blank line has its content one space after the marker
+        return Some(ListMarker {
+            ordered,
+            start,
+            delimiter,
+            content_offset: marker_width + 1,
+            content: String::new(),
+        });
+    }
+
+    if spacing > 4 {
+        // The content is an indented code block, so only one space belongs to the marker
+        return Some(ListMarker {
+            ordered,
+            start,
+            delimiter,
+            content_offset: marker_width + 1,
+            content: " ".repeat(spacing - 1) + content,
+        });
+    }
+
+    Some(ListMarker {
+        ordered,
+        start,
+        delimiter,
+        content_offset: marker_width + spacing,
+        content: content.to_string(),
+    })
+}
+
+fn parse_footnote_definition_start(line: &str) -> Option<(String, String)> {
+    lazy_static! {
+        static ref FOOTNOTE_DEFINITION_PATTERN: Regex =
+            Regex::new(r"^ {0,3}\[\^([^\]\s]+)\]:[ \t]*(.*)$").unwrap();
+    }
+
+    let caps = FOOTNOTE_DEFINITION_PATTERN.captures(line).ok()??;
+
+    Some((
+        caps.get(1).unwrap().as_str().to_string(),
+        caps.get(2).unwrap().as_str().to_string(),
+    ))
+}
+
+/// Parses a footnote definition, whose content continues on lines indented by 4 spaces
+fn parse_footnote_definition(lines: &[&str], first_line: usize) -> Option<(Element, usize)> {
+    let (label, content) = parse_footnote_definition_start(lines.first()?)?;
+
+    let mut builder = ContainerBuilder::default();
+    builder.add_line(content);
+    let mut line_count = 1;
+
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            builder.add_line(String::new());
+            continue;
+        }
+
+        if indentation_width(line) >= 4 {
+            builder.add_line(remove_indentation(line, 4));
+            line_count = index + 1;
+            continue;
+        }
+
+        // Lazy continuation of a paragraph inside the footnote
+        if !builder.last_line_is_blank()
+            && !starts_block(line)
+            && parse_footnote_definition_start(line).is_none()
+        {
+            builder.add_line(line.trim_start().to_string());
+            line_count = index + 1;
+            continue;
+        }
+
+        break;
+    }
+
+    let footnote_lines = builder.finish();
+    let (elements, _) = parse_blocks(
+        &footnote_lines
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>(),
+        first_line,
+    );
+
+    Some((Element::FootnoteDefinition { label, elements }, line_count))
+}
+
+/// Parses a list starting at the first line, returning it along with the number of lines
+/// consumed. Trailing blank lines are not part of the list.
+fn parse_list(lines: &[&str], in_paragraph: bool, first_line: usize) -> Option<(Element, usize)> {
+    let first_marker = parse_list_marker(lines.first()?)?;
+
+    // Only lists starting at 1 with a non-empty first item may interrupt a paragraph
+    if in_paragraph
+        && (first_marker.content.trim().is_empty()
+            || (first_marker.ordered && first_marker.start != 1))
+    {
+        return None;
+    }
+
+    let mut list_builder = ListBuilder::new(first_marker.ordered, first_marker.start);
+    let mut item_builder = ContainerBuilder::default();
+    item_builder.add_line(first_marker.content.clone());
+
+    let mut content_offset = first_marker.content_offset;
+    let mut line_count = 1;
+    let mut item_line = first_line;
+
+    let finish_item =
+        |item_builder: &mut ContainerBuilder, list_builder: &mut ListBuilder, item_line: usize| {
+            let mut item_lines = item_builder.finish();
+
+            let checked = match item_lines.first().and_then(|line| parse_task_marker(line)) {
+                Some((checked, content)) => {
+                    item_lines[0] = content;
+                    Some(checked)
+                }
+                None => None,
+            };
+
+            let (elements, loose) = parse_blocks(
+                &item_lines.iter().map(String::as_str).collect::<Vec<&str>>(),
+                item_line,
+            );
+
+            let item = match checked {
+                Some(checked) => ListItem::Task { checked, elements },
+                None => ListItem::Plain(elements),
+            };
+            list_builder.add_item(item, loose);
+        };
+
+    for (index, line) in lines.iter().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            // A list item can begin with at most one blank line
+            if item_builder.is_empty() {
+                break;
+            }
+
+            item_builder.add_line(String::new());
+            continue;
+        }
+
+        if indentation_width(line) >= content_offset {
+            item_builder.add_line(remove_indentation(line, content_offset));
+            line_count = index + 1;
+            continue;
+        }
+
+        if is_thematic_break(line) {
+            break;
+        }
+
+        match parse_list_marker(line) {
+            Some(marker) if marker.continues_list(&first_marker) => {
+                if item_builder.last_line_is_blank() {
+                    list_builder.set_loose();
+                }
+
+                finish_item(&mut item_builder, &mut list_builder, item_line);
+                item_builder.add_line(marker.content);
+                item_line = first_line + index;
+                content_offset = marker.content_offset;
+                line_count = index + 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Lazy continuation of a paragraph inside the item
+        if !item_builder.last_line_is_blank() && !starts_block(line) {
+            item_builder.add_line(line.trim_start().to_string());
+            line_count = index + 1;
+            continue;
+        }
+
+        break;
+    }
+
+    finish_item(&mut item_builder, &mut list_builder, item_line);
+
+    Some((list_builder.finish(), line_count))
+}
+
+/// Parses a definition marker (`: definition`), returning the definition along with the column
+/// that it starts at
+fn parse_definition_marker(line: &str) -> Option<(String, usize)> {
+    lazy_static! {
+        static ref DEFINITION_MARKER_PATTERN: Regex =
+            Regex::new(r"^( {0,3}:[ \t]+)(\S.*)$").unwrap();
+    }
+
+    let caps = DEFINITION_MARKER_PATTERN.captures(line).ok()??;
+    let marker = caps.get(1).unwrap().as_str().replacen(':', " ", 1);
+
+    Some((
+        caps.get(2).unwrap().as_str().to_string(),
+        indentation_width(&marker),
+    ))
+}
+
+/// Counts the lines at the start of `lines` that are the terms of a definition, along with whether
+/// the terms and the definition are separated by a blank line
+fn count_definition_terms(lines: &[&str]) -> Option<(usize, bool)> {
+    let term_count = lines
+        .iter()
+        .take_while(|line| {
+            !line.trim().is_empty()
+                && parse_definition_marker(line).is_none()
+                && !starts_block(line)
+        })
+        .count();
+
+    if term_count == 0 {
+        return None;
+    }
+
+    let is_definition = |index: usize| {
+        lines
+            .get(index)
+            .is_some_and(|line| parse_definition_marker(line).is_some())
+    };
+
+    if is_definition(term_count) {
+        Some((term_count, false))
+    } else if lines
+        .get(term_count)
+        .is_some_and(|line| line.trim().is_empty())
+        && is_definition(term_count + 1)
+    {
+        Some((term_count, true))
+    } else {
+        None
+    }
+}
+
+/// Parses a definition list whose first definition is on the first line, returning it along with
+/// the number of lines consumed. Definitions continue on lines indented as far as their content.
+fn parse_definition_list(
+    terms: Vec<Line>,
+    loose: bool,
+    lines: &[&str],
+    first_line: usize,
+) -> (Element, usize) {
+    let (content, mut content_offset) = parse_definition_marker(lines[0]).unwrap();
+
+    let mut items = vec![];
+    let mut item = DefinitionItem {
+        terms,
+        definitions: vec![],
+    };
+    let mut tight = !loose;
+
+    let mut definition_builder = ContainerBuilder::default();
+    definition_builder.add_line(content);
+    let mut definition_line = first_line;
+
+    let finish_definition =
+        |builder: &mut ContainerBuilder, item: &mut DefinitionItem, tight: &mut bool, line| {
+            let definition_lines = builder.finish();
+            let (elements, loose) = parse_blocks(
+                &definition_lines
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<&str>>(),
+                line,
+            );
+
+            if loose {
+                *tight = false;
+            }
+            item.definitions.push(elements);
+        };
+
+    let mut line_count = 1;
+    let mut index = 1;
+    while index < lines.len() {
+        let line = lines[index];
+
+        if line.trim().is_empty() {
+            definition_builder.add_line(String::new());
+            index += 1;
+            continue;
+        }
+
+        if indentation_width(line) >= content_offset {
+            definition_builder.add_line(remove_indentation(line, content_offset));
+            index += 1;
+            line_count = index;
+            continue;
+        }
+
+        if let Some((content, offset)) = parse_definition_marker(line) {
+            if definition_builder.last_line_is_blank() {
+                tight = false;
+            }
+
+            finish_definition(
+                &mut definition_builder,
+                &mut item,
+                &mut tight,
+                definition_line,
+            );
+            definition_builder.add_line(content);
+            definition_line = first_line + index;
+            content_offset = offset;
+            index += 1;
+            line_count = index;
+            continue;
+        }
+
+        // The terms of the next item
+        if let Some((term_count, blank_line)) = count_definition_terms(&lines[index..]) {
+            if blank_line {
+                tight = false;
+            }
+
+            finish_definition(
+                &mut definition_builder,
+                &mut item,
+                &mut tight,
+                definition_line,
+            );
+            items.push(mem::replace(
+                &mut item,
+                DefinitionItem {
+                    terms: (index..index + term_count)
+                        .map(|term| Line::from_str_at(lines[term].trim(), first_line + term))
+                        .collect(),
+                    definitions: vec![],
+                },
+            ));
+
+            index += term_count + usize::from(blank_line);
+            let (content, offset) = parse_definition_marker(lines[index]).unwrap();
+            definition_builder.add_line(content);
+            definition_line = first_line + index;
+            content_offset = offset;
+            index += 1;
+            line_count = index;
+            continue;
+        }
+
+        // Lazy continuation of a paragraph inside the definition
+        if !definition_builder.last_line_is_blank() && !starts_block(line) {
+            definition_builder.add_line(line.trim_start().to_string());
+            index += 1;
+            line_count = index;
+            continue;
+        }
+
+        break;
+    }
+
+    finish_definition(
+        &mut definition_builder,
+        &mut item,
+        &mut tight,
+        definition_line,
+    );
+    items.push(item);
+
+    (Element::DefinitionList { tight, items }, line_count)
+}
+
+/// Parses the `[ ]` or `[x]` at the start of a task list item, returning whether it is checked
+/// along with the rest of the line
+fn parse_task_marker(line: &str) -> Option<(bool, String)> {
+    lazy_static! {
+        static ref TASK_MARKER_PATTERN: Regex = Regex::new(r"^\[([ xX])\][ \t]+(\S.*)$").unwrap();
+    }
+
+    let caps = TASK_MARKER_PATTERN.captures(line).ok()??;
+
+    Some((
+        caps.get(1).unwrap().as_str() != " ",
+        caps.get(2).unwrap().as_str().to_string(),
+    ))
+}
+
+/// Counts the columns of leading whitespace, with tabs advancing to the next multiple of 4.
+fn indentation_width(line: &str) -> usize {
+    let mut width = 0;
+
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => break,
+        }
+    }
+
+    width
+}
+
+/// Removes up to `width` columns of leading whitespace, splitting tabs into spaces if needed.
+fn remove_indentation(line: &str, width: usize) -> String {
+    let mut column = 0;
+
+    for (index, c) in line.char_indices() {
+        if column >= width {
+            // A tab that no longer starts on a tab stop is expanded to keep the same width
+            if c == '\t' && column % 4 != 0 {
+                return " ".repeat(4 - column % 4) + &line[index + 1..];
+            }
+
+            return line[index..].to_string();
+        }
+
+        match c {
+            ' ' => column += 1,
+            '\t' => {
+                let tab_width = 4 - column % 4;
+                if column + tab_width > width {
+                    let remaining = " ".repeat(column + tab_width - width);
+                    return remaining + &line[index + 1..];
+                }
+
+                column += tab_width;
+            }
+            _ => return line[index..].to_string(),
+        }
+    }
+
+    String::new()
+}
+
+/// Normalizes a footnote or link label so that labels differing only in case or whitespace match
+pub fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn parse_line(line: &str, references: &LinkReferences) -> Line {
+    if line.trim().len() == 0 {
+        return Line::from_str("");
+    }
+
+    parse_inlines(line, references)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::document::{
+        Alignment, DefinitionItem, Document, Element, Footnote, InlineElement, Line, ListItem,
+    };
+    use crate::markdown::parser::{
+        parse, parse_header, parse_line, parse_list_marker, parse_task_marker, remove_indentation,
+    };
+    use crate::markdown::references::LinkReferences;
+    use crate::util::html::Attributes;
+    use std::fs;
+
+    /// A paragraph of plain text lines, which are joined by soft breaks
+    fn paragraph(lines: Vec<&str>) -> Element {
+        let mut line = Line::default();
+        for (index, text) in lines.into_iter().enumerate() {
+            if index > 0 {
+                line.elements.push(InlineElement::SoftBreak);
+            }
+            line.elements.push(InlineElement::Text(text.to_string()));
+        }
+
+        Element::Paragraph(vec![line], Attributes::default())
+    }
+
+    fn header(level: u32, id: &str, text: &str) -> Element {
+        Element::Header {
+            level,
+            id: id.to_string(),
+            line: Line::from_str(text),
+            attributes: Attributes::default(),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_headings_paragraphs() {
+        let raw_text = fs::read_to_string("test_data/markdown/headings_paragraphs.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    header(
+                        1,
+                        "test-of-my-markdown-parser",
+                        "Test of my markdown parser"
+                    ),
+                    paragraph(vec![
+                        "This is a test file for my markdown parser.",
+                        "This file only contains headings and paragraphs."
+                    ]),
+                    header(1, "this-is-a-second-header", "This is a second header"),
+                    Element::Paragraph(
+                        vec![Line::from_str("And more text under the header.")],
+                        Attributes::default()
+                    ),
+                    Element::Paragraph(
+                        vec![Line::from_str(
+                            "This is a separate paragraph under the same heading."
+                        )],
+                        Attributes::default()
+                    ),
+                    header(2, "here-is-a-sub-header", "Here is a sub-header"),
+                    paragraph(vec![
+                        "Random text here.",
+                        "A second line of random text.",
+                        "A third line of random text."
+                    ]),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let gen_header_prefix = |level: u32| String::from("#").repeat(level as usize);
+        assert_eq!(gen_header_prefix(1), "#");
+        assert_eq!(gen_header_prefix(2), "##");
+        assert_eq!(gen_header_prefix(3), "###");
+
+        let assert_header = |s: &str| {
+            for level in 1..10 {
+                let raw_text = format!("{} {}", gen_header_prefix(level), s);
+                assert_eq!(
+                    parse_header(&raw_text, 1),
+                    Some(Element::Header {
+                        level,
+                        id: String::new(),
+                        line: Line::from_str(s),
+                        attributes: Attributes::default(),
+                    })
+                );
+            }
+        };
+
+        assert_eq!(parse_header("", 1), None);
+        assert_eq!(parse_header("A test sentence", 1), None);
+
+        assert_header("");
+        assert_header("A test sentence");
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let assert_line = |s: &str| {
+            assert_eq!(parse_line(s, &LinkReferences::new()), Line::from_str(s));
+        };
+
+        assert_line("");
+        assert_line("A test sentence");
+    }
+
+    #[test]
+    fn test_parse_links() {
+        assert_eq!(
+            parse_line(
+                "See [the docs](https://example.com/docs) for more",
+                &LinkReferences::new()
+            ),
+            Line::new(vec![
+                InlineElement::Text("See ".to_string()),
+                InlineElement::Link {
+                    text: Line::from_str("the docs"),
+                    url: "https://example.com/docs".to_string(),
+                    title: None,
+                    attributes: Attributes::default(),
+                },
+                InlineElement::Text(" for more".to_string()),
+            ])
+        );
+
+        assert_eq!(
+            parse_line(
+                "[**Bold** link](/page \"Page title\")",
+                &LinkReferences::new()
+            ),
+            Line::new(vec![InlineElement::Link {
+                text: Line::new(vec![
+                    InlineElement::Emphasis(2, Line::from_str("Bold")),
+                    InlineElement::Text(" link".to_string()),
+                ]),
+                url: "/page".to_string(),
+                title: Some("Page title".to_string()),
+                attributes: Attributes::default(),
+            }])
+        );
+
+        assert_eq!(
+            parse_line(
+                "[Wiki](https://en.wikipedia.org/wiki/Rust_(programming_language))",
+                &LinkReferences::new()
+            ),
+            Line::new(vec![InlineElement::Link {
+                text: Line::from_str("Wiki"),
+                url: "https://en.wikipedia.org/wiki/Rust_(programming_language)".to_string(),
+                title: None,
+                attributes: Attributes::default(),
+            }])
+        );
+
+        assert_eq!(
+            parse_line("[not a link] (here)", &LinkReferences::new()),
+            Line::from_str("[not a link] (here)")
+        );
+    }
+
+    #[test]
+    fn test_parse_images() {
+        assert_eq!(
+            parse_line("![A cat](cat.png 'Cat')", &LinkReferences::new()),
+            Line::new(vec![InlineElement::Image {
+                alt: Line::from_str("A cat"),
+                url: "cat.png".to_string(),
+                title: Some("Cat".to_string()),
+                attributes: Attributes::default(),
+            }])
+        );
+
+        assert_eq!(
+            parse_line(
+                "[![Badge](<badge image.svg>)](https://example.com)",
+                &LinkReferences::new()
+            ),
+            Line::new(vec![InlineElement::Link {
+                text: Line::new(vec![InlineElement::Image {
+                    alt: Line::from_str("Badge"),
+                    url: "badge image.svg".to_string(),
+                    title: None,
+                    attributes: Attributes::default(),
+                }]),
+                url: "https://example.com".to_string(),
+                title: None,
+                attributes: Attributes::default(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_code_blocks() {
+        let raw_text = fs::read_to_string("test_data/markdown/code_blocks.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    Element::Paragraph(
+                        vec![Line::from_str("A fenced code block:")],
+                        Attributes::default()
+                    ),
+                    Element::CodeBlock {
+                        info: "rust".to_string(),
+                        lines: vec![
+                            "fn main() {".to_string(),
+                            "    println!(\"Hello,  world!\");".to_string(),
+                            "".to_string(),
+                            "}".to_string(),
+                        ],
+                        attributes: Attributes::default(),
+                    },
+                    Element::Paragraph(
+                        vec![Line::from_str("An indented code block:")],
+                        Attributes::default()
+                    ),
+                    Element::CodeBlock {
+                        info: String::new(),
+                        lines: vec![
+                            "let x = 1;".to_string(),
+                            "".to_string(),
+                            "  let y = 2;".to_string(),
+                        ],
+                        attributes: Attributes::default(),
+                    },
+                    Element::CodeBlock {
+                        info: String::new(),
+                        lines: vec!["~~~".to_string(), "# Not a header".to_string()],
+                        attributes: Attributes::default(),
+                    },
+                    paragraph(vec!["Indented lines", "continue a paragraph."]),
+                    Element::CodeBlock {
+                        info: "text".to_string(),
+                        lines: vec!["Unclosed fences run to the end".to_string()],
+                        attributes: Attributes::default(),
+                    },
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_remove_indentation() {
+        assert_eq!(remove_indentation("    code", 4), "code");
+        assert_eq!(remove_indentation("      code", 4), "  code");
+        assert_eq!(remove_indentation("  code", 4), "code");
+        assert_eq!(remove_indentation("\tcode", 4), "code");
+        assert_eq!(remove_indentation("  \tcode", 2), "  code");
+        assert_eq!(remove_indentation("", 4), "");
+    }
+
+    #[test]
+    fn test_parse_code_spans() {
+        assert_eq!(
+            parse_line(
+                "Call `snake_case_name` or `*not emphasis*`",
+                &LinkReferences::new()
+            ),
+            Line::new(vec![
+                InlineElement::Text("Call ".to_string()),
+                InlineElement::Code("snake_case_name".to_string()),
+                InlineElement::Text(" or ".to_string()),
+                InlineElement::Code("*not emphasis*".to_string()),
+            ])
+        );
+
+        assert_eq!(
+            parse_line("`` `backticks` `` and _emphasis_", &LinkReferences::new()),
+            Line::new(vec![
+                InlineElement::Code("`backticks`".to_string()),
+                InlineElement::Text(" and ".to_string()),
+                InlineElement::Emphasis(1, Line::from_str("emphasis")),
+            ])
+        );
+
+        assert_eq!(
+            parse_line("``unmatched`", &LinkReferences::new()),
+            Line::from_str("``unmatched`")
+        );
+        assert_eq!(
+            parse_line("`  `", &LinkReferences::new()),
+            Line::new(vec![InlineElement::Code("  ".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_lists() {
+        let raw_text = fs::read_to_string("test_data/markdown/lists.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    paragraph(vec!["A tight list:"]),
+                    Element::List {
+                        ordered: false,
+                        start: 1,
+                        tight: true,
+                        items: vec![
+                            ListItem::Plain(vec![paragraph(vec!["First item"])]),
+                            ListItem::Plain(vec![paragraph(vec![
+                                "Second item",
+                                "continues here",
+                                "lazily continued"
+                            ])]),
+                            ListItem::Plain(vec![
+                                paragraph(vec!["Third item"]),
+                                Element::List {
+                                    ordered: true,
+                                    start: 1,
+                                    tight: true,
+                                    items: vec![
+                                        ListItem::Plain(vec![paragraph(vec!["Nested ordered"])]),
+                                        ListItem::Plain(vec![paragraph(vec!["Second nested"])]),
+                                    ]
+                                },
+                            ]),
+                        ]
+                    },
+                    paragraph(vec!["A loose ordered list:"]),
+                    Element::List {
+                        ordered: true,
+                        start: 3,
+                        tight: false,
+                        items: vec![
+                            ListItem::Plain(vec![paragraph(vec!["Starts at three"])]),
+                            ListItem::Plain(vec![
+                                paragraph(vec!["Contains a code block:"]),
+                                Element::CodeBlock {
+                                    info: String::new(),
+                                    lines: vec!["let x = 1;".to_string()],
+                                    attributes: Attributes::default(),
+                                },
+                            ]),
+                        ]
+                    },
+                    Element::List {
+                        ordered: false,
+                        start: 1,
+                        tight: true,
+                        items: vec![ListItem::Plain(vec![paragraph(vec![
+                            "A new list with a different bullet"
+                        ])])]
+                    },
+                    Element::List {
+                        ordered: false,
+                        start: 1,
+                        tight: true,
+                        items: vec![ListItem::Plain(vec![paragraph(vec!["And another"])])]
+                    },
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_task_lists() {
+        let paragraph =
+            |text: &str| Element::Paragraph(vec![Line::from_str(text)], Attributes::default());
+
+        assert_eq!(
+            parse("- [ ] Write the parser\n- [x] Ship it\n- [X] Upper case\n- [] Not a task\n"),
+            Document {
+                elements: vec![Element::List {
+                    ordered: false,
+                    start: 1,
+                    tight: true,
+                    items: vec![
+                        ListItem::Task {
+                            checked: false,
+                            elements: vec![paragraph("Write the parser")]
+                        },
+                        ListItem::Task {
+                            checked: true,
+                            elements: vec![paragraph("Ship it")]
+                        },
+                        ListItem::Task {
+                            checked: true,
+                            elements: vec![paragraph("Upper case")]
+                        },
+                        ListItem::Plain(vec![paragraph("[] Not a task")]),
+                    ]
+                }],
+                footnotes: vec![],
+            }
+        );
+
+        assert_eq!(
+            parse_task_marker("[x] Done"),
+            Some((true, "Done".to_string()))
+        );
+        assert_eq!(
+            parse_task_marker("[ ]  Todo"),
+            Some((false, "Todo".to_string()))
+        );
+        assert_eq!(parse_task_marker("[ ]"), None);
+        assert_eq!(parse_task_marker("[x]Done"), None);
+        assert_eq!(parse_task_marker("[y] Done"), None);
+    }
+
+    #[test]
+    fn test_parse_with_footnotes() {
+        let raw_text = fs::read_to_string("test_data/markdown/footnotes.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        let text = |s: &str| InlineElement::Text(s.to_string());
+        let reference =
+            |label: &str, number: u32, occurrence: u32| InlineElement::FootnoteReference {
+                label: label.to_string(),
+                number,
+                occurrence,
+            };
+        let paragraph = |elements: Vec<InlineElement>| {
+            Element::Paragraph(vec![Line::new(elements)], Attributes::default())
+        };
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    paragraph(vec![
+                        text("Footnotes are numbered in order of reference"),
+                        reference("second", 1, 1),
+                        text(", not definition"),
+                        reference("first", 2, 1),
+                        text("."),
+                        InlineElement::SoftBreak,
+                        text("The first footnote is referenced again"),
+                        reference("second", 1, 2),
+                        text(" and a "),
+                        InlineElement::Emphasis(1, Line::from_str("nested")),
+                        reference("nested", 3, 1),
+                        text(" one follows."),
+                    ]),
+                    paragraph(vec![
+                        text("An "),
+                        text("[^undefined]"),
+                        text(" footnote stays as text."),
+                    ]),
+                ],
+                footnotes: vec![
+                    Footnote {
+                        label: "second".to_string(),
+                        elements: vec![
+                            paragraph(vec![text("A footnote with two paragraphs.")]),
+                            paragraph(vec![text("The second paragraph is indented.")]),
+                        ],
+                        references: 2,
+                    },
+                    Footnote {
+                        label: "first".to_string(),
+                        elements: vec![paragraph(vec![text("The first definition.")])],
+                        references: 1,
+                    },
+                    Footnote {
+                        label: "nested".to_string(),
+                        elements: vec![paragraph(vec![
+                            text("This footnote references "),
+                            reference("inner", 4, 1),
+                            text(" another."),
+                        ])],
+                        references: 1,
+                    },
+                    Footnote {
+                        label: "inner".to_string(),
+                        elements: vec![paragraph(vec![text("The inner footnote.")])],
+                        references: 1,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_reference_links() {
+        let raw_text = fs::read_to_string("test_data/markdown/reference_links.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        let text = |s: &str| InlineElement::Text(s.to_string());
+        let docs_link = |s: &str| InlineElement::Link {
+            text: Line::from_str(s),
+            url: "https://example.com/docs".to_string(),
+            title: Some("The docs".to_string()),
+            attributes: Attributes::default(),
+        };
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    Element::Paragraph(
+                        vec![Line::new(vec![
+                            text("See "),
+                            docs_link("the docs"),
+                            text(", the "),
+                            InlineElement::Link {
+                                text: Line::from_str("Changelog"),
+                                url: "https://example.com/changelog".to_string(),
+                                title: None,
+                                attributes: Attributes::default(),
+                            },
+                            text(" or just "),
+                            docs_link("docs"),
+                            text("."),
+                        ])],
+                        Attributes::default()
+                    ),
+                    Element::BlockQuote(vec![Element::Paragraph(
+                        vec![Line::new(vec![
+                            text("A "),
+                            docs_link("quoted"),
+                            text(" reference.")
+                        ])],
+                        Attributes::default()
+                    )]),
+                    Element::Paragraph(
+                        vec![Line::from_str(
+                            "[an undefined][nope] reference and [nope] stay as text."
+                        )],
+                        Attributes::default()
+                    ),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_list_marker() {
+        let marker = parse_list_marker("- item").unwrap();
+        assert_eq!((marker.ordered, marker.delimiter), (false, '-'));
+        assert_eq!(
+            (marker.content_offset, marker.content.as_str()),
+            (2, "item")
+        );
+
+        let marker = parse_list_marker("  12.   item").unwrap();
+        assert_eq!(
+            (marker.ordered, marker.start, marker.delimiter),
+            (true, 12, '.')
+        );
+        assert_eq!(
+            (marker.content_offset, marker.content.as_str()),
+            (8, "item")
+        );
+
+        let marker = parse_list_marker("1)      code").unwrap();
+        assert_eq!(
+            (marker.content_offset, marker.content.as_str()),
+            (3, "     code")
+        );
+
+        let marker = parse_list_marker("*").unwrap();
+        assert_eq!((marker.content_offset, marker.content.as_str()), (2, ""));
+
+        assert_eq!(parse_list_marker("-item"), None);
+        assert_eq!(parse_list_marker("    - indented code"), None);
+        assert_eq!(parse_list_marker("1234567890. too long"), None);
+        assert_eq!(parse_list_marker("Plain text"), None);
+    }
+
+    #[test]
+    fn test_parse_with_block_quotes() {
+        let raw_text = fs::read_to_string("test_data/markdown/block_quotes.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    Element::BlockQuote(vec![
+                        header(2, "a-quoted-header", "A quoted header"),
+                        paragraph(vec!["Quoted text", "with a lazy continuation line."]),
+                        Element::BlockQuote(vec![paragraph(vec![
+                            "A nested quote",
+                            "that continues."
+                        ])]),
+                        Element::List {
+                            ordered: false,
+                            start: 1,
+                            tight: true,
+                            items: vec![
+                                ListItem::Plain(vec![Element::Paragraph(
+                                    vec![Line::from_str("A quoted list")],
+                                    Attributes::default()
+                                )]),
+                                ListItem::Plain(vec![Element::Paragraph(
+                                    vec![Line::from_str("with two items")],
+                                    Attributes::default()
+                                )]),
+                            ]
+                        },
+                    ]),
+                    Element::Paragraph(
+                        vec![Line::from_str("Outside of the quote.")],
+                        Attributes::default()
+                    ),
+                    Element::BlockQuote(vec![Element::Paragraph(
+                        vec![Line::from_str("A second quote")],
+                        Attributes::default()
+                    )]),
+                    Element::BlockQuote(vec![Element::CodeBlock {
+                        info: String::new(),
+                        lines: vec!["code()".to_string()],
+                        attributes: Attributes::default(),
+                    }]),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_tables() {
+        let raw_text = fs::read_to_string("test_data/markdown/tables.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        let row = |cells: Vec<&str>| {
+            cells
+                .iter()
+                .map(|cell| Line::from_str(cell))
+                .collect::<Vec<Line>>()
+        };
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    Element::Table {
+                        alignments: vec![
+                            Alignment::Left,
+                            Alignment::Center,
+                            Alignment::Right,
+                            Alignment::None
+                        ],
+                        header: row(vec!["Left", "Center", "Right", "None"]),
+                        rows: vec![
+                            row(vec!["a", "b", "c", "d"]),
+                            row(vec!["pipe | escaped", "", "", ""]),
+                            row(vec!["too", "many", "cells", "in"]),
+                        ]
+                    },
+                    Element::Paragraph(
+                        vec![Line::from_str("Not part of the table")],
+                        Attributes::default()
+                    ),
+                    Element::Table {
+                        alignments: vec![Alignment::None],
+                        header: row(vec!["Single column"]),
+                        rows: vec![]
+                    },
+                    paragraph(vec!["Mismatched | columns", "--- | --- | ---"]),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_definition_lists() {
+        let raw_text = fs::read_to_string("test_data/markdown/definition_lists.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    Element::DefinitionList {
+                        tight: true,
+                        items: vec![
+                            DefinitionItem {
+                                terms: vec![Line::from_str("Apple")],
+                                definitions: vec![
+                                    vec![paragraph(vec!["A red fruit"])],
+                                    vec![paragraph(vec!["A technology company"])],
+                                ],
+                            },
+                            DefinitionItem {
+                                terms: vec![Line::from_str("Orange"), Line::from_str("Tangerine")],
+                                definitions: vec![vec![paragraph(vec![
+                                    "Citrus fruits",
+                                    "that are lazily continued"
+                                ])]],
+                            },
+                        ],
+                    },
+                    paragraph(vec!["A paragraph between the lists"]),
+                    Element::DefinitionList {
+                        tight: false,
+                        items: vec![DefinitionItem {
+                            terms: vec![Line::from_str("Term")],
+                            definitions: vec![vec![
+                                paragraph(vec!["A loose definition"]),
+                                paragraph(vec!["With a second paragraph"]),
+                            ]],
+                        }],
+                    },
+                ],
+                footnotes: vec![],
+            }
+        );
+
+        // The last paragraph runs to the end of the text, with no line after it
+        assert_eq!(
+            parse("Term\n: A definition.\n\nThat is all."),
+            Document {
+                elements: vec![
+                    Element::DefinitionList {
+                        tight: true,
+                        items: vec![DefinitionItem {
+                            terms: vec![Line::from_str("Term")],
+                            definitions: vec![vec![paragraph(vec!["A definition."])]],
+                        }],
+                    },
+                    paragraph(vec!["That is all."]),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_admonitions() {
+        let raw_text = fs::read_to_string("test_data/markdown/admonitions.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        let admonition = |kind: &str, title: &str, body: Vec<Element>| Element::Admonition {
+            kind: kind.to_string(),
+            title: Line::from_str(title),
+            body,
+        };
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    admonition("note", "Note", vec![paragraph(vec!["Useful information."])]),
+                    admonition(
+                        "warning",
+                        "Mind the gap",
+                        vec![paragraph(vec!["Between the train", "and the platform."])]
+                    ),
+                    Element::BlockQuote(vec![paragraph(vec![
+                        "[!not closed",
+                        "A plain block quote."
+                    ])]),
+                    admonition(
+                        "tip",
+                        "Tip",
+                        vec![
+                            paragraph(vec!["A tip."]),
+                            admonition("danger", "Nested", vec![paragraph(vec!["Inside."])]),
+                        ]
+                    ),
+                    admonition("info", "Info", vec![paragraph(vec!["Runs to the end"])]),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_math() {
+        let math = |source: &str, source_line| Element::MathBlock {
+            source: source.to_string(),
+            source_line,
+        };
+
+        assert_eq!(
+            parse("$$\\int_0^1 x\\,dx$$\n\n$$\na = b\n+ c\n$$\n\n```math\n\\sqrt{2}\n```\n\n$$\n\nNot math $$").elements,
+            vec![
+                math("\\int_0^1 x\\,dx", 1),
+                math("a = b\n+ c", 4),
+                math("\\sqrt{2}", 9),
+                Element::Paragraph(vec![Line::from_str("$$")], Attributes::default()),
+                Element::Paragraph(vec![Line::from_str("Not math $$")], Attributes::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_source_lines() {
+        let document = parse(
+            "Title\n=====\n\n| a |\n|---|\n| b |\n\nTerm\n: Definition\n\n:::note Heads up\nInside\n:::\n\nText[^1]\n\n[^1]: Footnote\n",
+        );
+
+        let mut source_lines = vec![];
+        document.for_each_line(&mut |line| source_lines.push(line.source_line));
+
+        assert_eq!(source_lines, vec![1, 4, 6, 8, 9, 11, 12, 15, 17]);
+    }
+
+    #[test]
+    fn test_parse_with_setext_headers_and_thematic_breaks() {
+        let raw_text =
+            fs::read_to_string("test_data/markdown/setext_headers_thematic_breaks.md").unwrap();
+        let parsed_doc = parse(&raw_text);
+
+        assert_eq!(
+            parsed_doc,
+            Document {
+                elements: vec![
+                    header(1, "a-setext-header", "A setext header"),
+                    Element::Paragraph(vec![Line::from_str("Some text.")], Attributes::default()),
+                    Element::Header {
+                        level: 2,
+                        id: "a-second-level-header-over-two-lines".to_string(),
+                        line: Line::new(vec![
+                            InlineElement::Text("A second level header".to_string()),
+                            InlineElement::SoftBreak,
+                            InlineElement::Text("over two lines".to_string()),
+                        ]),
+                        attributes: Attributes::default(),
+                    },
+                    Element::ThematicBreak,
+                    Element::Paragraph(
+                        vec![Line::from_str("Text between breaks")],
+                        Attributes::default()
+                    ),
+                    Element::ThematicBreak,
+                    Element::List {
+                        ordered: false,
+                        start: 1,
+                        tight: true,
+                        items: vec![ListItem::Plain(vec![Element::Paragraph(
+                            vec![Line::from_str("A list item")],
+                            Attributes::default()
+                        )])]
+                    },
+                    Element::ThematicBreak,
+                    Element::Paragraph(vec![Line::from_str("===")], Attributes::default()),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_escapes_and_entities() {
+        assert_eq!(
+            parse_line("\\*not emphasis\\* and 2 \\_ 3", &LinkReferences::new()),
+            Line::from_str("*not emphasis* and 2 _ 3")
+        );
+        assert_eq!(
+            parse_line(
+                "\\[not a link\\](url) \\`not code\\`",
+                &LinkReferences::new()
+            ),
+            Line::from_str("[not a link](url) `not code`")
+        );
+        assert_eq!(
+            parse_line(
+                "Tom &amp; Jerry &copy; &#35; &#x22; &nope; \\a",
+                &LinkReferences::new()
+            ),
+            Line::from_str("Tom & Jerry © # \" &nope; \\a")
+        );
+        assert_eq!(
+            parse_line("*emphasis with \\* inside*", &LinkReferences::new()),
+            Line::new(vec![InlineElement::Emphasis(
+                1,
+                Line::from_str("emphasis with * inside")
+            )])
+        );
+        assert_eq!(
+            parse_line("`\\*code keeps backslashes\\*`", &LinkReferences::new()),
+            Line::new(vec![InlineElement::Code(
+                "\\*code keeps backslashes\\*".to_string()
+            )])
+        );
+        assert_eq!(
+            parse_line(
+                "[link](/url?a=1&amp;b=\\* \"title &quot;here&quot;\")",
+                &LinkReferences::new()
+            ),
+            Line::new(vec![InlineElement::Link {
+                text: Line::from_str("link"),
+                url: "/url?a=1&b=*".to_string(),
+                title: Some("title \"here\"".to_string()),
+                attributes: Attributes::default(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_hard_breaks() {
+        let text = |s: &str| InlineElement::Text(s.to_string());
+
+        assert_eq!(
+            parse("Two spaces  \nA backslash\\\nEscaped \\\\\nOne space \nLast line  \n"),
+            Document {
+                elements: vec![Element::Paragraph(
+                    vec![Line::new(vec![
+                        text("Two spaces"),
+                        InlineElement::HardBreak,
+                        text("A backslash"),
+                        InlineElement::HardBreak,
+                        text("Escaped \\"),
+                        InlineElement::SoftBreak,
+                        text("One space"),
+                        InlineElement::SoftBreak,
+                        text("Last line"),
+                    ])],
+                    Attributes::default()
+                )],
+                footnotes: vec![],
+            }
+        );
+
+        assert_eq!(
+            parse("*Emphasis*\\\nnext\\"),
+            Document {
+                elements: vec![Element::Paragraph(
+                    vec![Line::new(vec![
+                        InlineElement::Emphasis(1, Line::from_str("Emphasis")),
+                        InlineElement::HardBreak,
+                        text("next\\"),
+                    ])],
+                    Attributes::default()
+                )],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_inlines_across_lines() {
+        let text = |s: &str| InlineElement::Text(s.to_string());
+
+        assert_eq!(
+            parse("A [link that\ncrosses](/url) a line and *emphasis  \nthat does* too.\n`code\nspan`"),
+            Document {
+                elements: vec![Element::Paragraph(
+                    vec![Line::new(vec![
+                        text("A "),
+                        InlineElement::Link {
+                            text: Line::new(vec![
+                                text("link that"),
+                                InlineElement::SoftBreak,
+                                text("crosses"),
+                            ]),
+                            url: "/url".to_string(),
+                            title: None,
+                            attributes: Attributes::default(),
+                        },
+                        text(" a line and "),
+                        InlineElement::Emphasis(
+                            1,
+                            Line::new(vec![
+                                text("emphasis"),
+                                InlineElement::HardBreak,
+                                text("that does"),
+                            ])
+                        ),
+                        text(" too."),
+                        InlineElement::SoftBreak,
+                        InlineElement::Code("code span".to_string()),
+                    ])],
+                    Attributes::default()
+                )],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_html_blocks() {
+        let html_block =
+            |lines: &[&str]| Element::HtmlBlock(lines.iter().map(|s| s.to_string()).collect());
+
+        assert_eq!(
+            parse("<div class=\"note\">\n\n*Markdown*\n\n</div>\n\n<!-- a\nb -->\ntext\n<pre>\ncode\n\n</pre>\n"),
+            Document {
+                elements: vec![
+                    html_block(&["<div class=\"note\">"]),
+                    Element::Paragraph(vec![Line::new(vec![InlineElement::Emphasis(1, Line::from_str("Markdown"))])], Attributes::default()),
+                    html_block(&["</div>"]),
+                    html_block(&["<!-- a", "b -->"]),
+                    Element::Paragraph(vec![Line::from_str("text")], Attributes::default()),
+                    html_block(&["<pre>", "code", "", "</pre>"]),
+                ],
+                footnotes: vec![],
+            }
+        );
+
+        // Tags of any other name must be on a line of their own, and cannot interrupt a paragraph
+        assert_eq!(
+            parse("<custom-tag a=\"1\">\n*a*\n\nText\n<custom-tag>\n\n<span>text</span>\n"),
+            Document {
+                elements: vec![
+                    html_block(&["<custom-tag a=\"1\">", "*a*"]),
+                    Element::Paragraph(
+                        vec![Line::new(vec![
+                            InlineElement::Text("Text".to_string()),
+                            InlineElement::SoftBreak,
+                            InlineElement::Html("<custom-tag>".to_string()),
+                        ])],
+                        Attributes::default()
+                    ),
+                    Element::Paragraph(
+                        vec![Line::new(vec![
+                            InlineElement::Html("<span>".to_string()),
+                            InlineElement::Text("text".to_string()),
+                            InlineElement::Html("</span>".to_string()),
+                        ])],
+                        Attributes::default()
+                    ),
+                ],
+                footnotes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_with_attributes() {
+        let class = |class: &str| Attributes {
+            classes: vec![class.to_string()],
+            ..Attributes::default()
+        };
+
+        assert_eq!(
+            parse("# Intro {#start .big}\n\nSetext {.small}\n---\n\nA paragraph\n{.lead}\n\n```rust {.numbered}\nfn main() {}\n```\n\n{.alone}\n"),
+            Document {
+                elements: vec![
+                    Element::Header {
+                        level: 1,
+                        id: "start".to_string(),
+                        line: Line::from_str("Intro"),
+                        attributes: class("big"),
+                    },
+                    Element::Header {
+                        level: 2,
+                        id: "setext".to_string(),
+                        line: Line::from_str("Setext"),
+                        attributes: class("small"),
+                    },
+                    Element::Paragraph(vec![Line::from_str("A paragraph")], class("lead")),
+                    Element::CodeBlock {
+                        info: "rust".to_string(),
+                        lines: vec!["fn main() {}".to_string()],
+                        attributes: class("numbered"),
+                    },
+                    Element::Paragraph(vec![Line::from_str("{.alone}")], Attributes::default()),
+                ],
+                footnotes: vec![],
+            }
+        );
     }
 }
@@ -0,0 +1,178 @@
+use crate::markdown::document::{Element, Line};
+use crate::markdown::inline_parser::unescape;
+use crate::markdown::parser::normalize_label;
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::mem;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LinkDefinition {
+    pub url: String,
+    pub title: Option<String>,
+}
+
+/// Link reference definitions keyed by their normalized label
+pub type LinkReferences = HashMap<String, LinkDefinition>;
+
+/// Removes link reference definitions (`[label]: url "title"`) from the start of every paragraph,
+/// including paragraphs in nested blocks. Paragraphs left empty are removed, and the first
+/// definition of a label wins.
+///
+/// This must run before inline parsing, while the lines of paragraphs are still raw text.
+pub fn take_link_definitions(elements: &mut Vec<Element>, references: &mut LinkReferences) {
+    for mut element in mem::take(elements) {
+        match &mut element {
+            Element::Paragraph(lines, _) => {
+                let text = lines
+                    .iter()
+                    .map(Line::to_plain_text)
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                let mut consumed = 0;
+                while let Some((label, definition, length)) =
+                    parse_link_definition(&text[consumed..])
+                {
+                    references
+                        .entry(normalize_label(&label))
+                        .or_insert(definition);
+                    consumed += length;
+                }
+
+                // Definitions always end at the end of a line
+                let consumed_lines = text[..consumed].matches('\n').count()
+                    + usize::from(consumed == text.len() && consumed > 0);
+                lines.drain(..consumed_lines.min(lines.len()));
+
+                if lines.is_empty() {
+                    continue;
+                }
+            }
+            Element::BlockQuote(children)
+            | Element::Admonition { body: children, .. }
+            | Element::FootnoteDefinition {
+                elements: children, ..
+            } => take_link_definitions(children, references),
+            Element::List { items, .. } => {
+                for item in items {
+                    take_link_definitions(item.elements_mut(), references);
+                }
+            }
+            Element::DefinitionList { items, .. } => {
+                for definition in items.iter_mut().flat_map(|item| &mut item.definitions) {
+                    take_link_definitions(definition, references);
+                }
+            }
+            _ => {}
+        }
+
+        elements.push(element);
+    }
+}
+
+/// Parses a link reference definition at the start of the text, returning its label and
+/// definition along with the number of bytes consumed (including the final line break).
+fn parse_link_definition(text: &str) -> Option<(String, LinkDefinition, usize)> {
+    lazy_static! {
+        // The title may be on the line after the destination. If anything other than whitespace
+        // follows the title, the definition is only valid if the title is on its own line.
+        static ref LINK_DEFINITION_PATTERN: Regex = Regex::new(
+            r#"^ {0,3}\[((?:[^\[\]\\]|\\.){1,999})\]:[ \t]*\n?[ \t]*(?:<((?:[^<>\n\\]|\\.)*)>|([^\s<][^\s]*))(?:(?:[ \t]+\n?|[ \t]*\n)[ \t]*(?:"((?:[^"\\]|\\.)*)"|'((?:[^'\\]|\\.)*)'|\(((?:[^()\\]|\\.)*)\)))?[ \t]*(?:\n|$)"#
+        )
+        .unwrap();
+    }
+
+    let caps = LINK_DEFINITION_PATTERN.captures(text).ok()??;
+
+    let label = caps.get(1).unwrap().as_str();
+    if label.trim().is_empty() {
+        return None;
+    }
+
+    let url = caps.get(2).or(caps.get(3)).unwrap().as_str();
+    let title = caps
+        .get(4)
+        .or(caps.get(5))
+        .or(caps.get(6))
+        .map(|m| unescape(m.as_str()));
+
+    Some((
+        label.to_string(),
+        LinkDefinition {
+            url: unescape(url),
+            title,
+        },
+        caps.get(0).unwrap().end(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::document::{Element, Line};
+    use crate::markdown::references::{
+        parse_link_definition, take_link_definitions, LinkDefinition, LinkReferences,
+    };
+    use crate::util::html::Attributes;
+
+    fn definition(url: &str, title: Option<&str>) -> LinkDefinition {
+        LinkDefinition {
+            url: url.to_string(),
+            title: title.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_link_definition() {
+        assert_eq!(
+            parse_link_definition("[Foo]: /url \"title\""),
+            Some(("Foo".to_string(), definition("/url", Some("title")), 19))
+        );
+        assert_eq!(
+            parse_link_definition("[foo]:\n<my url>\n'title'\nrest"),
+            Some(("foo".to_string(), definition("my url", Some("title")), 24))
+        );
+        assert_eq!(
+            parse_link_definition("[foo]: /url\n\"title\" trailing"),
+            Some(("foo".to_string(), definition("/url", None), 12))
+        );
+        assert_eq!(
+            parse_link_definition("[foo]: /url \"title\" trailing"),
+            None
+        );
+        assert_eq!(parse_link_definition("[foo]:"), None);
+        assert_eq!(parse_link_definition("[ ]: /url"), None);
+    }
+
+    #[test]
+    fn test_take_link_definitions() {
+        let mut references = LinkReferences::new();
+        let mut elements = vec![
+            Element::Paragraph(
+                vec![Line::from_str("[a]: /a"), Line::from_str("[B]: /b 'B'")],
+                Attributes::default(),
+            ),
+            Element::BlockQuote(vec![Element::Paragraph(
+                vec![Line::from_str("[a]: /ignored"), Line::from_str("Some text")],
+                Attributes::default(),
+            )]),
+        ];
+
+        take_link_definitions(&mut elements, &mut references);
+
+        assert_eq!(
+            elements,
+            vec![Element::BlockQuote(vec![Element::Paragraph(
+                vec![Line::from_str("Some text")],
+                Attributes::default()
+            )])]
+        );
+        assert_eq!(
+            references,
+            LinkReferences::from([
+                ("a".to_string(), definition("/a", None)),
+                ("b".to_string(), definition("/b", Some("B"))),
+            ])
+        );
+    }
+}
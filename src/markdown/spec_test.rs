@@ -0,0 +1,338 @@
+//! Runs the CommonMark spec examples in `test_data/commonmark_spec.json` through the parser and
+//! compares them against the spec's HTML using a minimal reference renderer.
+
+use crate::markdown::document::{Alignment, Document, Element, InlineElement, Line, ListItem};
+use crate::markdown::parser::parse;
+use crate::util::html::escape_html;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// The number of examples that are known to pass. Raise this as the parser improves, so that
+/// regressions are caught.
+const MINIMUM_PASSING_EXAMPLES: usize = 605;
+
+/// Renders a document the same way as the CommonMark reference implementation
+#[derive(Default)]
+struct HtmlRenderer {
+    output: String,
+}
+
+impl HtmlRenderer {
+    fn render(document: &Document) -> String {
+        let mut renderer = HtmlRenderer::default();
+        renderer.blocks(&document.elements, false);
+        renderer.output
+    }
+
+    /// Starts a new line if the output doesn't already end with one
+    fn cr(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+    }
+
+    fn blocks(&mut self, elements: &[Element], tight: bool) {
+        for element in elements {
+            self.block(element, tight);
+        }
+    }
+
+    fn block(&mut self, element: &Element, tight: bool) {
+        match element {
+            Element::Header { level, line, .. } => {
+                self.cr();
+                self.output.push_str(&format!("<h{}>", level));
+                self.line(line);
+                self.output.push_str(&format!("</h{}>", level));
+                self.cr();
+            }
+            Element::Paragraph(lines, _) => {
+                if !tight {
+                    self.cr();
+                    self.output.push_str("<p>");
+                }
+
+                for (i, line) in lines.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push('\n');
+                    }
+                    self.line(line);
+                }
+
+                if !tight {
+                    self.output.push_str("</p>");
+                    self.cr();
+                }
+            }
+            Element::ThematicBreak => {
+                self.cr();
+                self.output.push_str("<hr />");
+                self.cr();
+            }
+            Element::CodeBlock { info, lines, .. } => {
+                self.cr();
+                self.output.push_str("<pre><code");
+                if let Some(language) = info.split_whitespace().next() {
+                    self.output
+                        .push_str(&format!(" class=\"language-{}\"", escape_html(language)));
+                }
+                self.output.push('>');
+                for line in lines {
+                    self.output.push_str(&escape_html(line));
+                    self.output.push('\n');
+                }
+                self.output.push_str("</code></pre>");
+                self.cr();
+            }
+            Element::List {
+                ordered,
+                start,
+                tight,
+                items,
+            } => {
+                let tag = if *ordered { "ol" } else { "ul" };
+
+                self.cr();
+                if *ordered && *start != 1 {
+                    self.output.push_str(&format!("<ol start=\"{}\">", start));
+                } else {
+                    self.output.push_str(&format!("<{}>", tag));
+                }
+                self.cr();
+
+                for item in items {
+                    self.output.push_str("<li>");
+                    if let ListItem::Task { checked, .. } = item {
+                        self.output.push_str(if *checked {
+                            "<input checked=\"\" disabled=\"\" type=\"checkbox\" /> "
+                        } else {
+                            "<input disabled=\"\" type=\"checkbox\" /> "
+                        });
+                    }
+                    self.blocks(item.elements(), *tight);
+                    self.output.push_str("</li>");
+                    self.cr();
+                }
+
+                self.output.push_str(&format!("</{}>", tag));
+                self.cr();
+            }
+            Element::BlockQuote(elements) => {
+                self.cr();
+                self.output.push_str("<blockquote>");
+                self.cr();
+                self.blocks(elements, false);
+                self.cr();
+                self.output.push_str("</blockquote>");
+                self.cr();
+            }
+            Element::Admonition { kind, title, body } => {
+                self.cr();
+                self.output
+                    .push_str(&format!("<div class=\"admonition {}\">", escape_html(kind)));
+                self.wrap("p", title);
+                self.blocks(body, false);
+                self.output.push_str("</div>");
+                self.cr();
+            }
+            Element::Table {
+                alignments,
+                header,
+                rows,
+            } => {
+                self.cr();
+                self.output.push_str("<table>\n<thead>\n");
+                self.table_row(header, alignments, "th");
+                self.output.push_str("</thead>\n");
+                if !rows.is_empty() {
+                    self.output.push_str("<tbody>\n");
+                    for row in rows {
+                        self.table_row(row, alignments, "td");
+                    }
+                    self.output.push_str("</tbody>\n");
+                }
+                self.output.push_str("</table>");
+                self.cr();
+            }
+            Element::DefinitionList { tight, items } => {
+                self.cr();
+                self.output.push_str("<dl>");
+                self.cr();
+                for item in items {
+                    for term in &item.terms {
+                        self.wrap("dt", term);
+                        self.cr();
+                    }
+                    for definition in &item.definitions {
+                        self.output.push_str("<dd>");
+                        self.blocks(definition, *tight);
+                        self.output.push_str("</dd>");
+                        self.cr();
+                    }
+                }
+                self.output.push_str("</dl>");
+                self.cr();
+            }
+            Element::MathBlock { source, .. } => {
+                self.cr();
+                self.output.push_str(&format!(
+                    "<div class=\"math\">{}</div>",
+                    escape_html(source)
+                ));
+                self.cr();
+            }
+            Element::HtmlBlock(lines) => {
+                self.cr();
+                for line in lines {
+                    self.output.push_str(line);
+                    self.output.push('\n');
+                }
+            }
+            Element::FootnoteDefinition { .. } => {}
+        }
+    }
+
+    fn table_row(&mut self, cells: &[Line], alignments: &[Alignment], tag: &str) {
+        self.output.push_str("<tr>\n");
+        for (cell, alignment) in cells.iter().zip(alignments) {
+            match alignment {
+                Alignment::None => self.output.push_str(&format!("<{}>", tag)),
+                Alignment::Left => self.output.push_str(&format!("<{} align=\"left\">", tag)),
+                Alignment::Center => self.output.push_str(&format!("<{} align=\"center\">", tag)),
+                Alignment::Right => self.output.push_str(&format!("<{} align=\"right\">", tag)),
+            }
+            self.line(cell);
+            self.output.push_str(&format!("</{}>\n", tag));
+        }
+        self.output.push_str("</tr>\n");
+    }
+
+    fn line(&mut self, line: &Line) {
+        for element in &line.elements {
+            self.inline(element);
+        }
+    }
+
+    fn wrap(&mut self, tag: &str, line: &Line) {
+        self.output.push_str(&format!("<{}>", tag));
+        self.line(line);
+        self.output.push_str(&format!("</{}>", tag));
+    }
+
+    fn inline(&mut self, element: &InlineElement) {
+        match element {
+            InlineElement::Text(text) => self.output.push_str(&escape_html(text)),
+            InlineElement::Code(code) => {
+                self.output
+                    .push_str(&format!("<code>{}</code>", escape_html(code)));
+            }
+            InlineElement::Emphasis(level, line) => {
+                self.wrap(if *level == 1 { "em" } else { "strong" }, line);
+            }
+            InlineElement::Strikethrough(line) => self.wrap("del", line),
+            InlineElement::Highlight(line) => self.wrap("mark", line),
+            InlineElement::Superscript(line) => self.wrap("sup", line),
+            InlineElement::Subscript(line) => self.wrap("sub", line),
+            InlineElement::Html(html) => self.output.push_str(html),
+            InlineElement::SoftBreak => self.output.push('\n'),
+            InlineElement::HardBreak => self.output.push_str("<br />\n"),
+            InlineElement::Math { source, .. } => {
+                self.output.push_str(&format!(
+                    "<span class=\"math\">{}</span>",
+                    escape_html(source)
+                ));
+            }
+            InlineElement::Emoji { emoji, .. } => self.output.push_str(emoji),
+            InlineElement::Link {
+                text, url, title, ..
+            } => {
+                self.output
+                    .push_str(&format!("<a href=\"{}\"", normalize_url(url)));
+                if let Some(title) = title {
+                    self.output
+                        .push_str(&format!(" title=\"{}\"", escape_html(title)));
+                }
+                self.output.push('>');
+                self.line(text);
+                self.output.push_str("</a>");
+            }
+            InlineElement::Image {
+                alt, url, title, ..
+            } => {
+                self.output.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\"",
+                    normalize_url(url),
+                    escape_html(&alt.to_plain_text())
+                ));
+                if let Some(title) = title {
+                    self.output
+                        .push_str(&format!(" title=\"{}\"", escape_html(title)));
+                }
+                self.output.push_str(" />");
+            }
+            InlineElement::FootnoteReference { number, .. } => {
+                self.output.push_str(&format!(
+                    "<sup class=\"footnote-ref\"><a href=\"#fn:{0}\">{0}</a></sup>",
+                    number
+                ));
+            }
+        }
+    }
+}
+
+/// Percent-encodes a URL the way the reference implementation does, leaving existing escapes alone
+fn normalize_url(url: &str) -> String {
+    let bytes = url.as_bytes();
+    let mut normalized = String::new();
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        let is_escape = byte == b'%'
+            && bytes.len() > i + 2
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+
+        if byte.is_ascii_alphanumeric() || b";/?:@&=+$,-_.!~*'()#".contains(&byte) || is_escape {
+            normalized.push(byte as char);
+        } else {
+            normalized.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    escape_html(&normalized)
+}
+
+#[test]
+fn test_commonmark_spec() {
+    let spec = fs::read_to_string("test_data/commonmark_spec.json").unwrap();
+    let examples: Vec<serde_json::Value> = serde_json::from_str(&spec).unwrap();
+
+    // Section name -> (passed, total), in alphabetical order
+    let mut sections: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+
+    for example in &examples {
+        let markdown = example["markdown"].as_str().unwrap();
+        let html = example["html"].as_str().unwrap();
+        let section = sections
+            .entry(example["section"].as_str().unwrap())
+            .or_default();
+
+        section.1 += 1;
+        if HtmlRenderer::render(&parse(markdown)) == html {
+            section.0 += 1;
+        }
+    }
+
+    for (name, (passed, total)) in &sections {
+        println!("{:>3}/{:<3} {}", passed, total, name);
+    }
+
+    let passed: usize = sections.values().map(|(passed, _)| passed).sum();
+    println!("{:>3}/{:<3} Total", passed, examples.len());
+
+    assert!(
+        passed >= MINIMUM_PASSING_EXAMPLES,
+        "only {} CommonMark examples passed, expected at least {}",
+        passed,
+        MINIMUM_PASSING_EXAMPLES
+    );
+}
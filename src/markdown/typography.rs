@@ -0,0 +1,150 @@
+use crate::markdown::document::{Document, InlineElement};
+
+const NO_BREAK_SPACE: char = '\u{a0}';
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+
+/// Replaces straight quotes with curly ones, `--` and `---` with en and em dashes, and `...` with
+/// an ellipsis. For French (`fr`), double quotes become guillemets and non-breaking spaces are put
+/// before `;`, `:`, `!` and `?`.
+///
+/// Only text is changed, so code, math and raw HTML are left as they are.
+pub fn apply_smart_punctuation(document: &mut Document, language: &str) {
+    let french = language == "fr" || language.starts_with("fr-") || language.starts_with("fr_");
+
+    document.for_each_line_mut(&mut |line| {
+        // Quotes are opened or closed depending on the character before them, which may be in an
+        // earlier element of the line
+        let mut previous = None;
+
+        line.for_each_element_mut(&mut |inline| match inline {
+            InlineElement::Text(text) => *text = smarten(text, &mut previous, french),
+            InlineElement::Code(_)
+            | InlineElement::Math { .. }
+            | InlineElement::Emoji { .. }
+            | InlineElement::FootnoteReference { .. } => previous = Some('x'),
+            InlineElement::SoftBreak | InlineElement::HardBreak => previous = None,
+            _ => {}
+        });
+    });
+}
+
+fn smarten(text: &str, previous: &mut Option<char>, french: bool) -> String {
+    let text = text
+        .replace("---", "\u{2014}")
+        .replace("--", "\u{2013}")
+        .replace("...", "\u{2026}");
+    let chars: Vec<char> = text.chars().collect();
+
+    let mut smart = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        let next = chars.get(index + 1).copied();
+        let opening = previous.is_none_or(|p| {
+            p.is_whitespace() || "([{-\u{2013}\u{2014}\u{201c}\u{2018}«".contains(p)
+        });
+
+        match c {
+            '"' if french && opening => {
+                smart.push('«');
+                smart.push(NO_BREAK_SPACE);
+            }
+            '"' if french => {
+                push_no_break_space(&mut smart, NO_BREAK_SPACE);
+                smart.push('»');
+            }
+            '"' => smart.push(if opening { '\u{201c}' } else { '\u{201d}' }),
+            // An apostrophe can start a word when letters are left out, as in '90s
+            '\'' if opening && !next.is_some_and(|c| c.is_ascii_digit()) => smart.push('\u{2018}'),
+            '\'' => smart.push('\u{2019}'),
+            ';' | '!' | '?' if french => {
+                if smart.ends_with(' ') || ends_word(*previous, next) {
+                    push_no_break_space(&mut smart, NARROW_NO_BREAK_SPACE);
+                }
+                smart.push(c);
+            }
+            // Colons without a space after them are left alone, as in times and URLs
+            ':' | '»' if french => {
+                if smart.ends_with(' ') || ends_word(*previous, next) {
+                    push_no_break_space(&mut smart, NO_BREAK_SPACE);
+                }
+                smart.push(c);
+            }
+            ' ' if french && *previous == Some('«') => smart.push(NO_BREAK_SPACE),
+            c => smart.push(c),
+        }
+
+        *previous = smart.chars().last();
+    }
+
+    smart
+}
+
+/// Returns true if punctuation between `previous` and `next` directly follows a word and ends it
+fn ends_word(previous: Option<char>, next: Option<char>) -> bool {
+    previous.is_some_and(char::is_alphanumeric) && next.is_none_or(char::is_whitespace)
+}
+
+/// Replaces a space at the end of the text with a non-breaking one, or adds one if there is none
+fn push_no_break_space(text: &mut String, space: char) {
+    if text.ends_with(' ') {
+        text.pop();
+    }
+
+    if !text.ends_with([NO_BREAK_SPACE, NARROW_NO_BREAK_SPACE]) {
+        text.push(space);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::document::InlineElement;
+    use crate::markdown::parser::parse;
+    use crate::markdown::typography::apply_smart_punctuation;
+
+    fn smarten(markdown: &str, language: &str) -> String {
+        let mut document = parse(markdown);
+        apply_smart_punctuation(&mut document, language);
+
+        document.to_string()
+    }
+
+    #[test]
+    fn test_apply_smart_punctuation() {
+        assert_eq!(
+            smarten("\"Don't\" -- she said --- 'wait...' in the '90s", "en"),
+            "\u{201c}Don\u{2019}t\u{201d} \u{2013} she said \u{2014} \u{2018}wait\u{2026}\u{2019} in the \u{2019}90s\n"
+        );
+        assert_eq!(
+            smarten("\"*Quoted*\" and (\"brackets\")", "en"),
+            "\u{201c}Emphasis[1](Quoted)\u{201d} and (\u{201c}brackets\u{201d})\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_smart_punctuation_skips_code() {
+        let mut document = parse("`\"code\" -- ...` and $a'$\n\n```\n\"block\"\n```");
+        apply_smart_punctuation(&mut document, "en");
+
+        assert_eq!(
+            document.to_string(),
+            "Code(\"code\" -- ...) and Math(a')\nCodeBlock[]\n\"block\"\n"
+        );
+
+        let mut document = parse("[\"link\"](https://example.com/a--b)");
+        apply_smart_punctuation(&mut document, "en");
+        document.elements[0].for_each_line_mut(&mut |line| {
+            assert!(matches!(
+                &line.elements[0],
+                InlineElement::Link { url, .. } if url == "https://example.com/a--b"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_apply_smart_punctuation_french() {
+        assert_eq!(
+            smarten("\"Bonjour\" ! Il est 12:30; voir https://example.com : « oui »", "fr"),
+            "«\u{a0}Bonjour\u{a0}»\u{202f}! Il est 12:30\u{202f}; voir https://example.com\u{a0}: «\u{a0}oui\u{a0}»\n"
+        );
+        assert_eq!(smarten("Quoi? Non!", "en"), "Quoi? Non!\n");
+    }
+}
@@ -1,5 +1,10 @@
-use crate::markdown::document::{Document, Element, InlineElement, Line};
+use crate::markdown::document::{
+    Alignment, Document, Element, Footnote, InlineElement, Line, ListItem,
+};
+use crate::markdown::parser::ParseOptions;
 use crate::template::template_element::TemplateElement;
+use crate::util::html::{contains_script, encode_entities, is_javascript_url, Attributes};
+use crate::util::mathml::tex_to_mathml;
 use crate::{markdown, Template};
 use std::fs;
 use std::path::PathBuf;
@@ -9,6 +14,35 @@ pub struct Pages {
     pages: Vec<Page>,
 }
 
+#[derive(Debug)]
+pub struct RenderOptions {
+    /// Encodes `mailto:` links and their text with HTML entities
+    pub obfuscate_emails: bool,
+    /// Escapes raw HTML containing scripts or event handlers instead of passing it through, and
+    /// replaces `javascript:` URLs of links and images with `#`
+    pub no_scripts: bool,
+    /// Adds a link to itself after every header
+    pub heading_anchors: bool,
+    /// Renders emoji with the `emoji.html` template instead of as plain text
+    pub emoji_template: bool,
+    /// The range of header levels included in the table of contents
+    pub toc_min_level: u32,
+    pub toc_max_level: u32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            obfuscate_emails: false,
+            no_scripts: false,
+            heading_anchors: false,
+            emoji_template: false,
+            toc_min_level: 1,
+            toc_max_level: 6,
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Page {
     name: String,
@@ -17,15 +51,15 @@ struct Page {
 }
 
 impl Pages {
-    pub fn load(path: &PathBuf) -> Self {
+    pub fn load(path: &PathBuf, options: &ParseOptions) -> Self {
         let mut pages = Pages { pages: vec![] };
 
-        pages.load_pages(path);
+        pages.load_pages(path, options);
 
         return pages;
     }
 
-    fn load_pages(&mut self, path: &PathBuf) {
+    fn load_pages(&mut self, path: &PathBuf, options: &ParseOptions) {
         for entry_res in fs::read_dir(path).unwrap() {
             let entry = match entry_res {
                 Ok(x) => x,
@@ -58,21 +92,29 @@ impl Pages {
                     continue;
                 }
 
-                self.pages.push(Page::load(&tag_name, &entry.path()));
+                self.pages
+                    .push(Page::load(&tag_name, &entry.path(), options));
             }
         }
     }
 
-    pub fn render(&self, template: &Template) -> Vec<(String, String)> {
+    pub fn render(&self, template: &Template, options: &RenderOptions) -> Vec<(String, String)> {
         self.pages
             .iter()
-            .map(|page| (page.name.clone(), page.render(template)))
+            .map(|page| (page.name.clone(), page.render(template, options)))
             .collect()
     }
 }
 
 impl Page {
-    fn load(tag: &str, path: &PathBuf) -> Self {
+    fn load(tag: &str, path: &PathBuf, options: &ParseOptions) -> Self {
+        let raw_text = fs::read_to_string(path).unwrap();
+        let document = markdown::parser::parse_with_options(&raw_text, options);
+
+        for warning in math_warnings(&raw_text, &document) {
+            eprintln!("warning: {}:{}", path.display(), warning);
+        }
+
         Page {
             name: path
                 .as_path()
@@ -81,50 +123,617 @@ impl Page {
                 .to_string_lossy()
                 .into_owned(),
             tag: tag.to_string(),
-            document: markdown::parser::parse(&fs::read_to_string(path).unwrap()),
+            document,
         }
     }
 
-    fn render(&self, template: &Template) -> String {
+    fn render(&self, template: &Template, options: &RenderOptions) -> String {
         let mut page_template = template.get_page(&self.tag).unwrap().clone();
         page_template.add("title", TemplateElement::Text("Article".to_string()));
+        page_template.add(
+            "toc",
+            markdown_toc_to_template_element(&self.document, options),
+        );
 
         for element in &self.document.elements {
-            page_template.add("content", markdown_element_to_template_element(element));
+            page_template.add(
+                "content",
+                markdown_element_to_template_element(element, options),
+            );
+        }
+
+        if !self.document.footnotes.is_empty() {
+            page_template.add(
+                "content",
+                markdown_footnotes_to_template_element(&self.document.footnotes, options),
+            );
+        }
+
+        // A page made only of definitions has no blocks, but its content is still set
+        if self.document.elements.is_empty() && self.document.footnotes.is_empty() {
+            page_template.add("content", TemplateElement::Text(String::new()));
         }
 
         return page_template.render().unwrap();
     }
 }
 
-fn markdown_element_to_template_element(element: &Element) -> TemplateElement {
+/// Finds the math in the document that can't be fully converted to MathML, returning a warning for
+/// each problem along with the line of the page that the math is on
+fn math_warnings(raw_text: &str, document: &Document) -> Vec<String> {
+    let mut math = vec![];
+    document.for_each_block(&mut |block| {
+        if let Element::MathBlock {
+            source,
+            source_line,
+        } = block
+        {
+            math.push((source.clone(), true, *source_line));
+        }
+    });
+    document.for_each_line(&mut |line| {
+        // The lines of a paragraph are joined, so the line the math is on is found by counting the
+        // line breaks before it
+        let mut source_line = line.source_line;
+        line.for_each_element(&mut |inline| match inline {
+            InlineElement::SoftBreak | InlineElement::HardBreak => source_line += 1,
+            InlineElement::Math { source, display } => {
+                math.push((source.clone(), *display, source_line));
+                source_line += source.matches('\n').count();
+            }
+            InlineElement::Html(html) => source_line += html.matches('\n').count(),
+            _ => {}
+        })
+    });
+
+    math.sort_by_key(|(_, _, source_line)| *source_line);
+
+    let lines: Vec<&str> = raw_text.lines().collect();
+    let mut warnings = vec![];
+    for (source, display, source_line) in math {
+        for warning in tex_to_mathml(&source, display).1 {
+            warnings.push(
+                match source_line
+                    .checked_sub(1)
+                    .and_then(|index| lines.get(index))
+                {
+                    Some(line) => format!(
+                        "{}: {}\n{:>5} | {}",
+                        source_line, warning, source_line, line
+                    ),
+                    None => format!(" {} in `{}`", warning, source),
+                },
+            );
+        }
+    }
+
+    warnings
+}
+
+/// Builds a table of contents from the top level headers of the document, nesting each header under
+/// the closest previous one of a lower level
+fn markdown_toc_to_template_element(
+    document: &Document,
+    options: &RenderOptions,
+) -> TemplateElement {
+    let headers: Vec<(u32, &String, &Line)> = document
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::Header {
+                level, id, line, ..
+            } if (options.toc_min_level..=options.toc_max_level).contains(level) => {
+                Some((*level, id, line))
+            }
+            _ => None,
+        })
+        .collect();
+
+    if headers.is_empty() {
+        return TemplateElement::Text(String::new());
+    }
+
+    TemplateElement::Toc(markdown_toc_entries(&headers))
+}
+
+fn markdown_toc_entries(headers: &[(u32, &String, &Line)]) -> Vec<TemplateElement> {
+    let mut entries = vec![];
+
+    let mut index = 0;
+    while index < headers.len() {
+        let (level, id, line) = headers[index];
+        let end = headers[index + 1..]
+            .iter()
+            .position(|(other_level, _, _)| *other_level <= level)
+            .map_or(headers.len(), |position| index + 1 + position);
+
+        entries.push(TemplateElement::TocEntry {
+            id: id.clone(),
+            content: vec![TemplateElement::Text(line.to_plain_text())],
+            children: markdown_toc_entries(&headers[index + 1..end]),
+        });
+        index = end;
+    }
+
+    entries
+}
+
+fn markdown_footnotes_to_template_element(
+    footnotes: &[Footnote],
+    options: &RenderOptions,
+) -> TemplateElement {
+    TemplateElement::Footnotes(
+        footnotes
+            .iter()
+            .enumerate()
+            .map(|(index, footnote)| TemplateElement::Footnote {
+                number: index as u32 + 1,
+                references: footnote.references,
+                content: footnote
+                    .elements
+                    .iter()
+                    .map(|element| markdown_element_to_template_element(element, options))
+                    .collect(),
+            })
+            .collect(),
+    )
+}
+
+fn markdown_element_to_template_element(
+    element: &Element,
+    options: &RenderOptions,
+) -> TemplateElement {
+    markdown_block_to_template_element(element, false, options)
+}
+
+/// Converts a block element, where `tight` is true for the direct contents of a tight list item
+fn markdown_block_to_template_element(
+    element: &Element,
+    tight: bool,
+    options: &RenderOptions,
+) -> TemplateElement {
     match element {
-        Element::Header(level, line) => {
-            TemplateElement::Header(*level, markdown_line_to_template_elements(line))
+        Element::Header {
+            level,
+            id,
+            line,
+            attributes,
+        } => TemplateElement::Header {
+            level: *level,
+            id: id.clone(),
+            anchor: options.heading_anchors,
+            content: markdown_line_to_template_elements(line, options),
+            attributes: markdown_attributes(attributes, options),
+        },
+        Element::Paragraph(lines, attributes) => {
+            let lines = lines
+                .iter()
+                .map(|line| {
+                    TemplateElement::Line(markdown_line_to_template_elements(line, options))
+                })
+                .collect();
+
+            if tight {
+                TemplateElement::TightParagraph(lines, markdown_attributes(attributes, options))
+            } else {
+                TemplateElement::Paragraph(lines, markdown_attributes(attributes, options))
+            }
         }
-        Element::Paragraph(lines) => TemplateElement::Paragraph(
-            lines
+        Element::ThematicBreak => TemplateElement::ThematicBreak,
+        Element::MathBlock { source, .. } => TemplateElement::Math {
+            mathml: tex_to_mathml(source, true).0,
+            display: true,
+        },
+        Element::CodeBlock {
+            info,
+            lines,
+            attributes,
+        } => TemplateElement::CodeBlock {
+            language: info.split_whitespace().next().unwrap_or("").to_string(),
+            content: lines.iter().map(|line| format!("{}\n", line)).collect(),
+            attributes: markdown_attributes(attributes, options),
+        },
+        Element::List {
+            ordered,
+            start,
+            tight,
+            items,
+        } => TemplateElement::List {
+            ordered: *ordered,
+            start: *start,
+            items: items
                 .iter()
-                .map(|line| TemplateElement::Line(markdown_line_to_template_elements(line)))
+                .map(|item| {
+                    let content = item
+                        .elements()
+                        .iter()
+                        .map(|element| markdown_block_to_template_element(element, *tight, options))
+                        .collect();
+
+                    match item {
+                        ListItem::Plain(_) => TemplateElement::ListItem(content),
+                        ListItem::Task { checked, .. } => TemplateElement::TaskItem {
+                            checked: *checked,
+                            content,
+                        },
+                    }
+                })
+                .collect(),
+        },
+        Element::DefinitionList { tight, items } => TemplateElement::DefinitionList(
+            items
+                .iter()
+                .flat_map(|item| {
+                    let terms = item.terms.iter().map(|term| {
+                        TemplateElement::DefinitionTerm(markdown_line_to_template_elements(
+                            term, options,
+                        ))
+                    });
+                    let definitions = item.definitions.iter().map(|definition| {
+                        TemplateElement::DefinitionDescription(
+                            definition
+                                .iter()
+                                .map(|element| {
+                                    markdown_block_to_template_element(element, *tight, options)
+                                })
+                                .collect(),
+                        )
+                    });
+
+                    terms.chain(definitions)
+                })
                 .collect(),
         ),
+        Element::Admonition { kind, title, body } => TemplateElement::Admonition {
+            kind: kind.clone(),
+            title: markdown_line_to_template_elements(title, options),
+            content: body
+                .iter()
+                .map(|element| markdown_element_to_template_element(element, options))
+                .collect(),
+        },
+        Element::BlockQuote(elements) => TemplateElement::BlockQuote(
+            elements
+                .iter()
+                .map(|element| markdown_element_to_template_element(element, options))
+                .collect(),
+        ),
+        Element::Table {
+            alignments,
+            header,
+            rows,
+        } => TemplateElement::Table {
+            header: Box::new(markdown_table_row_to_template_element(
+                header, alignments, true, options,
+            )),
+            rows: rows
+                .iter()
+                .map(|row| markdown_table_row_to_template_element(row, alignments, false, options))
+                .collect(),
+        },
+        Element::HtmlBlock(lines) => markdown_html_to_template_element(&lines.join("\n"), options),
+        Element::FootnoteDefinition { .. } => {
+            unreachable!("footnote definitions are moved out of the document when it is parsed")
+        }
     }
 }
 
-fn markdown_line_to_template_elements(line: &Line) -> Vec<TemplateElement> {
+fn markdown_html_to_template_element(html: &str, options: &RenderOptions) -> TemplateElement {
+    if options.no_scripts && contains_script(html) {
+        TemplateElement::Text(html.to_string())
+    } else {
+        TemplateElement::Html(html.to_string())
+    }
+}
+
+fn markdown_attributes(attributes: &Attributes, options: &RenderOptions) -> Attributes {
+    if options.no_scripts {
+        attributes.without_scripts()
+    } else {
+        attributes.clone()
+    }
+}
+
+/// Replaces a `javascript:` URL of a link or image with `#` if scripts are removed
+fn markdown_url(url: &str, options: &RenderOptions) -> String {
+    if options.no_scripts && is_javascript_url(url) {
+        "#".to_string()
+    } else {
+        url.to_string()
+    }
+}
+
+fn markdown_table_row_to_template_element(
+    row: &[Line],
+    alignments: &[Alignment],
+    header: bool,
+    options: &RenderOptions,
+) -> TemplateElement {
+    TemplateElement::TableRow(
+        row.iter()
+            .zip(alignments)
+            .map(|(cell, alignment)| TemplateElement::TableCell {
+                header,
+                align: match alignment {
+                    Alignment::None => "",
+                    Alignment::Left => "left",
+                    Alignment::Center => "center",
+                    Alignment::Right => "right",
+                }
+                .to_string(),
+                content: markdown_line_to_template_elements(cell, options),
+            })
+            .collect(),
+    )
+}
+
+fn markdown_line_to_template_elements(
+    line: &Line,
+    options: &RenderOptions,
+) -> Vec<TemplateElement> {
     line.elements
         .iter()
-        .map(|element| markdown_inline_element_to_template_element(element))
+        .map(|element| markdown_inline_element_to_template_element(element, options))
         .collect()
 }
 
-fn markdown_inline_element_to_template_element(element: &InlineElement) -> TemplateElement {
+fn markdown_inline_element_to_template_element(
+    element: &InlineElement,
+    options: &RenderOptions,
+) -> TemplateElement {
     match element {
         InlineElement::Text(text) => TemplateElement::Text(text.clone()),
+        InlineElement::Code(code) => TemplateElement::Code(code.clone()),
+        InlineElement::Html(html) => markdown_html_to_template_element(html, options),
+        InlineElement::SoftBreak => TemplateElement::SoftBreak,
+        InlineElement::HardBreak => TemplateElement::HardBreak,
+        InlineElement::Math { source, display } => TemplateElement::Math {
+            mathml: tex_to_mathml(source, *display).0,
+            display: *display,
+        },
+        InlineElement::Emoji { shortcode, emoji } => {
+            if options.emoji_template {
+                TemplateElement::Emoji {
+                    shortcode: shortcode.clone(),
+                    emoji: emoji.clone(),
+                }
+            } else {
+                TemplateElement::Text(emoji.clone())
+            }
+        }
         InlineElement::Emphasis(level, line) => match level {
             0 => unreachable!(),
-            1 => TemplateElement::Italic(markdown_line_to_template_elements(line)),
-            _ => TemplateElement::Bold(markdown_line_to_template_elements(line)),
+            1 => TemplateElement::Italic(markdown_line_to_template_elements(line, options)),
+            _ => TemplateElement::Bold(markdown_line_to_template_elements(line, options)),
         },
+        InlineElement::Strikethrough(line) => {
+            TemplateElement::Strikethrough(markdown_line_to_template_elements(line, options))
+        }
+        InlineElement::Highlight(line) => {
+            TemplateElement::Highlight(markdown_line_to_template_elements(line, options))
+        }
+        InlineElement::Superscript(line) => {
+            TemplateElement::Superscript(markdown_line_to_template_elements(line, options))
+        }
+        InlineElement::Subscript(line) => {
+            TemplateElement::Subscript(markdown_line_to_template_elements(line, options))
+        }
+        InlineElement::Link {
+            text,
+            url,
+            title,
+            attributes,
+        } => {
+            if options.obfuscate_emails && url.starts_with("mailto:") {
+                TemplateElement::Link {
+                    url: url.clone(),
+                    title: title.clone().unwrap_or_default(),
+                    content: vec![TemplateElement::Html(encode_entities(
+                        &text.to_plain_text(),
+                    ))],
+                    obfuscate: true,
+                    attributes: markdown_attributes(attributes, options),
+                }
+            } else {
+                TemplateElement::Link {
+                    url: markdown_url(url, options),
+                    title: title.clone().unwrap_or_default(),
+                    content: markdown_line_to_template_elements(text, options),
+                    obfuscate: false,
+                    attributes: markdown_attributes(attributes, options),
+                }
+            }
+        }
+        InlineElement::Image {
+            alt,
+            url,
+            title,
+            attributes,
+        } => TemplateElement::Image {
+            url: markdown_url(url, options),
+            title: title.clone().unwrap_or_default(),
+            alt: alt.to_plain_text(),
+            attributes: markdown_attributes(attributes, options),
+        },
+        InlineElement::FootnoteReference {
+            number, occurrence, ..
+        } => TemplateElement::FootnoteReference {
+            number: *number,
+            occurrence: *occurrence,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::markdown::parser::parse;
+    use crate::pages::{
+        markdown_element_to_template_element, markdown_footnotes_to_template_element,
+        markdown_toc_to_template_element, math_warnings, Page, RenderOptions,
+    };
+    use crate::template::template_element::TemplateElementTemplates;
+    use crate::Template;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_markdown_toc_to_template_element() {
+        let document = parse("# A\n### B\n## C\n> # Quoted\n# D\n#### E\n");
+        let templates = TemplateElementTemplates::default();
+
+        assert_eq!(
+            markdown_toc_to_template_element(&document, &RenderOptions::default())
+                .render(&templates)
+                .unwrap(),
+            "<ul><li><a href=\"#a\">A</a><ul><li><a href=\"#b\">B</a></li><li><a href=\"#c\">C</a></li></ul></li><li><a href=\"#d\">D</a><ul><li><a href=\"#e\">E</a></li></ul></li></ul>"
+        );
+
+        let options = RenderOptions {
+            toc_min_level: 2,
+            toc_max_level: 3,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            markdown_toc_to_template_element(&document, &options)
+                .render(&templates)
+                .unwrap(),
+            "<ul><li><a href=\"#b\">B</a></li><li><a href=\"#c\">C</a></li></ul>"
+        );
+
+        let options = RenderOptions {
+            toc_min_level: 5,
+            ..RenderOptions::default()
+        };
+        assert_eq!(
+            markdown_toc_to_template_element(&document, &options)
+                .render(&templates)
+                .unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_no_scripts_attributes() {
+        let document = parse("# Head {onclick=\"alert(1)\" .big}\n");
+        let templates = TemplateElementTemplates::default();
+        let render = |options: &RenderOptions| {
+            markdown_element_to_template_element(&document.elements[0], options)
+                .render(&templates)
+                .unwrap()
+        };
+
+        assert!(render(&RenderOptions::default()).contains("onclick"));
+
+        let options = RenderOptions {
+            no_scripts: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(render(&options), "<h1 id=\"head\" class=\"big\">Head</h1>");
+    }
+
+    #[test]
+    fn test_no_scripts_urls() {
+        let templates = TemplateElementTemplates::default();
+        let render = |markdown: &str, options: &RenderOptions| {
+            markdown_element_to_template_element(&parse(markdown).elements[0], options)
+                .render(&templates)
+                .unwrap()
+        };
+        let options = RenderOptions {
+            no_scripts: true,
+            ..RenderOptions::default()
+        };
+
+        for markdown in [
+            "[x](javascript:alert(1))",
+            "<javascript:alert(2)>",
+            "![x](javascript:alert(3))",
+            "[x]( JavaScript:alert(4))",
+        ] {
+            let html = render(markdown, &RenderOptions::default());
+            assert!(html.to_lowercase().contains("=\"javascript:"), "{}", html);
+
+            // The text of an autolink is left as it is, since it isn't run
+            let html = render(markdown, &options);
+            assert!(!html.to_lowercase().contains("=\"javascript:"), "{}", html);
+            assert!(html.contains("\"#\""), "{}", html);
+        }
+    }
+
+    #[test]
+    fn test_math_warnings() {
+        let raw_text = "Fine $x^2$ math\n\n> Not $\\foo{x}$ here\n\n$$\n\\frac{1}{2\n$$\n";
+        let document = parse(raw_text);
+
+        assert_eq!(
+            math_warnings(raw_text, &document),
+            vec![
+                "3: unsupported command `\\foo`\n    3 | > Not $\\foo{x}$ here",
+                "6: missing closing brace `}`\n    6 | \\frac{1}{2",
+            ]
+        );
+
+        // The same math in code or earlier in the page doesn't affect the line that is reported
+        let raw_text = "```\n$\\foo{x}$\n```\n\n- Not $\\foo{x}$ here\n- > Again $\\foo{x}$\n";
+        let document = parse(raw_text);
+
+        assert_eq!(
+            math_warnings(raw_text, &document),
+            vec![
+                "5: unsupported command `\\foo`\n    5 | - Not $\\foo{x}$ here",
+                "6: unsupported command `\\foo`\n    6 | - > Again $\\foo{x}$",
+            ]
+        );
+
+        // Math in a later line of a paragraph is reported at that line
+        let raw_text = "A paragraph\nover *three\nlines* with $\\foo{x}$\n";
+        let document = parse(raw_text);
+
+        assert_eq!(
+            math_warnings(raw_text, &document),
+            vec!["3: unsupported command `\\foo`\n    3 | lines* with $\\foo{x}$"]
+        );
+    }
+
+    #[test]
+    fn test_render_page_of_definitions() {
+        let template = Template::load("basic_site", &PathBuf::from("examples/basic_site/template"));
+
+        for markdown in ["[^x]: a [^x]\n", "[link]: /url\n", ""] {
+            let page = Page {
+                name: "definitions".to_string(),
+                tag: "article".to_string(),
+                document: parse(markdown),
+            };
+
+            assert!(page
+                .render(&template, &RenderOptions::default())
+                .contains("<body>\n\n</body>"));
+        }
+    }
+
+    #[test]
+    fn test_footnote_ids_dont_collide_with_headers() {
+        let document = parse("# fn 1\n\n# fnref 1\n\nText[^a]\n\n[^a]: Note\n");
+        let templates = TemplateElementTemplates::default();
+        let options = RenderOptions::default();
+
+        let mut html = String::new();
+        for element in &document.elements {
+            html += &markdown_element_to_template_element(element, &options)
+                .render(&templates)
+                .unwrap();
+        }
+        html += &markdown_footnotes_to_template_element(&document.footnotes, &options)
+            .render(&templates)
+            .unwrap();
+
+        for id in ["fn-1", "fnref-1", "fn:1", "fnref:1"] {
+            assert_eq!(
+                html.matches(&format!("id=\"{}\"", id)).count(),
+                1,
+                "{}",
+                html
+            );
+        }
     }
 }
@@ -1,5 +1,6 @@
 use crate::template::template_errors::TemplateError;
-use crate::template::template_string::TemplateString;
+use crate::template::template_string::{TemplateString, TemplateStringSection};
+use crate::util::html::{encode_entities, escape_html, optional_attribute, Attributes};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::fs;
@@ -8,26 +9,170 @@ use std::path::PathBuf;
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum TemplateElementType {
     Header,
+    HeaderAnchor,
     Paragraph,
+    TightParagraph,
+    ThematicBreak,
+    HardBreak,
+    SoftBreak,
     Line,
     Text,
+    Html,
+    Code,
+    Math,
+    Emoji,
     Italic,
     Bold,
+    Strikethrough,
+    Highlight,
+    Superscript,
+    Subscript,
+    Link,
+    Image,
+    CodeBlock,
+    List,
+    ListItem,
+    TaskItem,
+    DefinitionList,
+    DefinitionTerm,
+    DefinitionDescription,
+    BlockQuote,
+    Admonition,
+    Table,
+    TableRow,
+    TableCell,
+    FootnoteReference,
+    Footnotes,
+    Footnote,
+    FootnoteBackref,
+    Toc,
+    TocEntry,
 }
 
 #[derive(Debug, Clone)]
 pub enum TemplateElement {
-    Header(u32, Vec<TemplateElement>),
-    Paragraph(Vec<TemplateElement>),
+    Header {
+        level: u32,
+        id: String,
+        /// Adds a link to the header itself, rendered as `{anchor}`
+        anchor: bool,
+        content: Vec<TemplateElement>,
+        attributes: Attributes,
+    },
+    HeaderAnchor {
+        id: String,
+    },
+    Paragraph(Vec<TemplateElement>, Attributes),
+    /// A paragraph inside of a tight list, which is rendered without `<p>` tags by default
+    TightParagraph(Vec<TemplateElement>, Attributes),
+    ThematicBreak,
+    HardBreak,
+    /// The break between the lines of a paragraph
+    SoftBreak,
     Line(Vec<TemplateElement>),
     Text(String),
+    /// HTML that is inserted without being escaped
+    Html(String),
+    Code(String),
+    /// Math that has already been converted to MathML
+    Math {
+        mathml: String,
+        display: bool,
+    },
+    Emoji {
+        shortcode: String,
+        emoji: String,
+    },
     Italic(Vec<TemplateElement>),
     Bold(Vec<TemplateElement>),
+    Strikethrough(Vec<TemplateElement>),
+    Highlight(Vec<TemplateElement>),
+    Superscript(Vec<TemplateElement>),
+    Subscript(Vec<TemplateElement>),
+    Link {
+        url: String,
+        title: String,
+        content: Vec<TemplateElement>,
+        /// Encodes the URL with HTML entities, which is used to hide email addresses
+        obfuscate: bool,
+        attributes: Attributes,
+    },
+    Image {
+        url: String,
+        title: String,
+        alt: String,
+        attributes: Attributes,
+    },
+    CodeBlock {
+        language: String,
+        content: String,
+        attributes: Attributes,
+    },
+    List {
+        ordered: bool,
+        start: u32,
+        items: Vec<TemplateElement>,
+    },
+    ListItem(Vec<TemplateElement>),
+    TaskItem {
+        checked: bool,
+        content: Vec<TemplateElement>,
+    },
+    DefinitionList(Vec<TemplateElement>),
+    DefinitionTerm(Vec<TemplateElement>),
+    /// A definition, which may contain several blocks
+    DefinitionDescription(Vec<TemplateElement>),
+    BlockQuote(Vec<TemplateElement>),
+    /// A callout such as a note or a warning, rendered with `admonition_<kind>.html` if there is
+    /// one and `admonition.html` otherwise
+    Admonition {
+        kind: String,
+        title: Vec<TemplateElement>,
+        content: Vec<TemplateElement>,
+    },
+    Table {
+        header: Box<TemplateElement>,
+        rows: Vec<TemplateElement>,
+    },
+    TableRow(Vec<TemplateElement>),
+    TableCell {
+        header: bool,
+        /// One of `left`, `center` or `right`, or empty if the column has no alignment
+        align: String,
+        content: Vec<TemplateElement>,
+    },
+    FootnoteReference {
+        number: u32,
+        /// Counts the references to the same footnote, starting at 1
+        occurrence: u32,
+    },
+    /// The list of footnotes at the end of a page
+    Footnotes(Vec<TemplateElement>),
+    Footnote {
+        number: u32,
+        /// The number of references to the footnote, each of which gets a backreference link
+        references: u32,
+        content: Vec<TemplateElement>,
+    },
+    FootnoteBackref {
+        number: u32,
+        occurrence: u32,
+    },
+    /// A table of contents, which is also used for the entries nested under another entry
+    Toc(Vec<TemplateElement>),
+    TocEntry {
+        id: String,
+        content: Vec<TemplateElement>,
+        /// Entries for the headers under this one, rendered as `{children}`
+        children: Vec<TemplateElement>,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct TemplateElementTemplates {
     templates: HashMap<TemplateElementType, TemplateString>,
+    /// Templates for specific kinds of admonition, loaded from `admonition_<kind>.html`
+    admonitions: HashMap<String, TemplateString>,
 }
 
 impl Default for TemplateElementTemplates {
@@ -36,11 +181,35 @@ impl Default for TemplateElementTemplates {
             templates: HashMap::from([
                 (
                     TemplateElementType::Header,
-                    TemplateString::parse_string("<h{level}>{content}</h{level}>"),
+                    TemplateString::parse_string(
+                        "<h{level} id=\"{id}\"{attrs}>{content}{anchor}</h{level}>",
+                    ),
+                ),
+                (
+                    TemplateElementType::HeaderAnchor,
+                    TemplateString::parse_string(
+                        " <a href=\"#{id}\" class=\"header-anchor\">#</a>",
+                    ),
                 ),
                 (
                     TemplateElementType::Paragraph,
-                    TemplateString::parse_string("<p>{content}</p>"),
+                    TemplateString::parse_string("<p{attrs}>{content}</p>"),
+                ),
+                (
+                    TemplateElementType::TightParagraph,
+                    TemplateString::parse_string("{content}"),
+                ),
+                (
+                    TemplateElementType::ThematicBreak,
+                    TemplateString::parse_string("<hr>"),
+                ),
+                (
+                    TemplateElementType::HardBreak,
+                    TemplateString::parse_string("<br>"),
+                ),
+                (
+                    TemplateElementType::SoftBreak,
+                    TemplateString::parse_string(" "),
                 ),
                 (
                     TemplateElementType::Line,
@@ -50,6 +219,24 @@ impl Default for TemplateElementTemplates {
                     TemplateElementType::Text,
                     TemplateString::parse_string("{content}"),
                 ),
+                (
+                    TemplateElementType::Html,
+                    TemplateString::parse_string("{content}"),
+                ),
+                (
+                    TemplateElementType::Code,
+                    TemplateString::parse_string("<code>{content}</code>"),
+                ),
+                (
+                    TemplateElementType::Math,
+                    TemplateString::parse_string("{content}"),
+                ),
+                (
+                    TemplateElementType::Emoji,
+                    TemplateString::parse_string(
+                        "<span class=\"emoji\" role=\"img\" aria-label=\"{name}\">{emoji}</span>",
+                    ),
+                ),
                 (
                     TemplateElementType::Italic,
                     TemplateString::parse_string("<i>{content}</i>"),
@@ -58,7 +245,124 @@ impl Default for TemplateElementTemplates {
                     TemplateElementType::Bold,
                     TemplateString::parse_string("<b>{content}</b>"),
                 ),
+                (
+                    TemplateElementType::Strikethrough,
+                    TemplateString::parse_string("<del>{content}</del>"),
+                ),
+                (
+                    TemplateElementType::Highlight,
+                    TemplateString::parse_string("<mark>{content}</mark>"),
+                ),
+                (
+                    TemplateElementType::Superscript,
+                    TemplateString::parse_string("<sup>{content}</sup>"),
+                ),
+                (
+                    TemplateElementType::Subscript,
+                    TemplateString::parse_string("<sub>{content}</sub>"),
+                ),
+                (
+                    TemplateElementType::Link,
+                    TemplateString::parse_string(
+                        "<a href=\"{url}\"{title_attr}{attrs}>{content}</a>",
+                    ),
+                ),
+                (
+                    TemplateElementType::Image,
+                    TemplateString::parse_string(
+                        "<img src=\"{url}\" alt=\"{alt}\"{title_attr}{attrs}>",
+                    ),
+                ),
+                (
+                    TemplateElementType::CodeBlock,
+                    TemplateString::parse_string(
+                        "<pre{attrs}><code{language_attr}>{content}</code></pre>",
+                    ),
+                ),
+                (
+                    TemplateElementType::List,
+                    TemplateString::parse_string("<{tag}{start_attr}>{content}</{tag}>"),
+                ),
+                (
+                    TemplateElementType::ListItem,
+                    TemplateString::parse_string("<li>{content}</li>"),
+                ),
+                (
+                    TemplateElementType::TaskItem,
+                    TemplateString::parse_string(
+                        "<li class=\"task-list-item\"><input type=\"checkbox\" disabled{checked_attr}> {content}</li>",
+                    ),
+                ),
+                (
+                    TemplateElementType::DefinitionList,
+                    TemplateString::parse_string("<dl>{content}</dl>"),
+                ),
+                (
+                    TemplateElementType::DefinitionTerm,
+                    TemplateString::parse_string("<dt>{content}</dt>"),
+                ),
+                (
+                    TemplateElementType::DefinitionDescription,
+                    TemplateString::parse_string("<dd>{content}</dd>"),
+                ),
+                (
+                    TemplateElementType::BlockQuote,
+                    TemplateString::parse_string("<blockquote>{content}</blockquote>"),
+                ),
+                (
+                    TemplateElementType::Admonition,
+                    TemplateString::parse_string(
+                        "<div class=\"admonition admonition-{kind}\"><p class=\"admonition-title\">{title}</p>{content}</div>",
+                    ),
+                ),
+                (
+                    TemplateElementType::Table,
+                    TemplateString::parse_string(
+                        "<table><thead>{header}</thead><tbody>{content}</tbody></table>",
+                    ),
+                ),
+                (
+                    TemplateElementType::TableRow,
+                    TemplateString::parse_string("<tr>{content}</tr>"),
+                ),
+                (
+                    TemplateElementType::TableCell,
+                    TemplateString::parse_string("<{tag}{align_attr}>{content}</{tag}>"),
+                ),
+                (
+                    TemplateElementType::FootnoteReference,
+                    TemplateString::parse_string(
+                        "<sup class=\"footnote-ref\"><a href=\"#{id}\" id=\"{ref_id}\">{number}</a></sup>",
+                    ),
+                ),
+                (
+                    TemplateElementType::Footnotes,
+                    TemplateString::parse_string(
+                        "<section class=\"footnotes\"><ol>{content}</ol></section>",
+                    ),
+                ),
+                (
+                    TemplateElementType::Footnote,
+                    TemplateString::parse_string("<li id=\"{id}\">{content}{backrefs}</li>"),
+                ),
+                (
+                    TemplateElementType::FootnoteBackref,
+                    TemplateString::parse_string(
+                        " <a href=\"#{ref_id}\" class=\"footnote-backref\">\u{21a9}</a>",
+                    ),
+                ),
+                (
+                    TemplateElementType::Toc,
+                    TemplateString::parse_string("<ul>{content}</ul>"),
+                ),
+                (
+                    TemplateElementType::TocEntry,
+                    TemplateString::parse_string(
+                        "<li><a href=\"#{id}\">{content}</a>{children}</li>",
+                    ),
+                ),
             ]),
+            admonitions: HashMap::new(),
         }
     }
 }
@@ -67,11 +371,44 @@ impl TemplateElementType {
     fn from_str(s: &str) -> Option<Self> {
         match s {
             "header" => Some(TemplateElementType::Header),
+            "header_anchor" => Some(TemplateElementType::HeaderAnchor),
             "paragraph" => Some(TemplateElementType::Paragraph),
+            "tight_paragraph" => Some(TemplateElementType::TightParagraph),
+            "hr" => Some(TemplateElementType::ThematicBreak),
+            "br" => Some(TemplateElementType::HardBreak),
+            "soft_break" => Some(TemplateElementType::SoftBreak),
             "line" => Some(TemplateElementType::Line),
             "text" => Some(TemplateElementType::Text),
+            "html" => Some(TemplateElementType::Html),
+            "code" => Some(TemplateElementType::Code),
+            "math" => Some(TemplateElementType::Math),
+            "emoji" => Some(TemplateElementType::Emoji),
             "italic" => Some(TemplateElementType::Italic),
             "bold" => Some(TemplateElementType::Bold),
+            "strike" => Some(TemplateElementType::Strikethrough),
+            "mark" => Some(TemplateElementType::Highlight),
+            "sup" => Some(TemplateElementType::Superscript),
+            "sub" => Some(TemplateElementType::Subscript),
+            "link" => Some(TemplateElementType::Link),
+            "image" => Some(TemplateElementType::Image),
+            "code_block" => Some(TemplateElementType::CodeBlock),
+            "list" => Some(TemplateElementType::List),
+            "list_item" => Some(TemplateElementType::ListItem),
+            "task_item" => Some(TemplateElementType::TaskItem),
+            "dl" => Some(TemplateElementType::DefinitionList),
+            "dt" => Some(TemplateElementType::DefinitionTerm),
+            "dd" => Some(TemplateElementType::DefinitionDescription),
+            "blockquote" => Some(TemplateElementType::BlockQuote),
+            "admonition" => Some(TemplateElementType::Admonition),
+            "table" => Some(TemplateElementType::Table),
+            "table_row" => Some(TemplateElementType::TableRow),
+            "table_cell" => Some(TemplateElementType::TableCell),
+            "footnote_ref" => Some(TemplateElementType::FootnoteReference),
+            "footnotes" => Some(TemplateElementType::Footnotes),
+            "footnote" => Some(TemplateElementType::Footnote),
+            "footnote_backref" => Some(TemplateElementType::FootnoteBackref),
+            "toc" => Some(TemplateElementType::Toc),
+            "toc_entry" => Some(TemplateElementType::TocEntry),
             _ => None,
         }
     }
@@ -80,45 +417,308 @@ impl TemplateElementType {
 impl TemplateElement {
     pub fn to_type(&self) -> TemplateElementType {
         match self {
-            TemplateElement::Header(_, _) => TemplateElementType::Header,
-            TemplateElement::Paragraph(_) => TemplateElementType::Paragraph,
+            TemplateElement::Header { .. } => TemplateElementType::Header,
+            TemplateElement::HeaderAnchor { .. } => TemplateElementType::HeaderAnchor,
+            TemplateElement::Paragraph(..) => TemplateElementType::Paragraph,
+            TemplateElement::TightParagraph(..) => TemplateElementType::TightParagraph,
+            TemplateElement::ThematicBreak => TemplateElementType::ThematicBreak,
+            TemplateElement::HardBreak => TemplateElementType::HardBreak,
+            TemplateElement::SoftBreak => TemplateElementType::SoftBreak,
             TemplateElement::Line(_) => TemplateElementType::Line,
             TemplateElement::Text(_) => TemplateElementType::Text,
+            TemplateElement::Html(_) => TemplateElementType::Html,
+            TemplateElement::Code(_) => TemplateElementType::Code,
+            TemplateElement::Math { .. } => TemplateElementType::Math,
+            TemplateElement::Emoji { .. } => TemplateElementType::Emoji,
             TemplateElement::Italic(_) => TemplateElementType::Italic,
             TemplateElement::Bold(_) => TemplateElementType::Bold,
+            TemplateElement::Strikethrough(_) => TemplateElementType::Strikethrough,
+            TemplateElement::Highlight(_) => TemplateElementType::Highlight,
+            TemplateElement::Superscript(_) => TemplateElementType::Superscript,
+            TemplateElement::Subscript(_) => TemplateElementType::Subscript,
+            TemplateElement::Link { .. } => TemplateElementType::Link,
+            TemplateElement::Image { .. } => TemplateElementType::Image,
+            TemplateElement::CodeBlock { .. } => TemplateElementType::CodeBlock,
+            TemplateElement::List { .. } => TemplateElementType::List,
+            TemplateElement::ListItem(_) => TemplateElementType::ListItem,
+            TemplateElement::TaskItem { .. } => TemplateElementType::TaskItem,
+            TemplateElement::DefinitionList(_) => TemplateElementType::DefinitionList,
+            TemplateElement::DefinitionTerm(_) => TemplateElementType::DefinitionTerm,
+            TemplateElement::DefinitionDescription(_) => TemplateElementType::DefinitionDescription,
+            TemplateElement::BlockQuote(_) => TemplateElementType::BlockQuote,
+            TemplateElement::Admonition { .. } => TemplateElementType::Admonition,
+            TemplateElement::Table { .. } => TemplateElementType::Table,
+            TemplateElement::TableRow(_) => TemplateElementType::TableRow,
+            TemplateElement::TableCell { .. } => TemplateElementType::TableCell,
+            TemplateElement::FootnoteReference { .. } => TemplateElementType::FootnoteReference,
+            TemplateElement::Footnotes(_) => TemplateElementType::Footnotes,
+            TemplateElement::Footnote { .. } => TemplateElementType::Footnote,
+            TemplateElement::FootnoteBackref { .. } => TemplateElementType::FootnoteBackref,
+            TemplateElement::Toc(_) => TemplateElementType::Toc,
+            TemplateElement::TocEntry { .. } => TemplateElementType::TocEntry,
         }
     }
 
     pub fn render(&self, templates: &TemplateElementTemplates) -> Result<String, TemplateError> {
-        let mut template = match templates.get(self.to_type()) {
+        let template = match self {
+            TemplateElement::Admonition { kind, .. } => templates.get_admonition(kind),
+            _ => templates.get(self.to_type()),
+        };
+        let mut template = match template {
             Some(t) => t.clone(),
             None => return Err(TemplateError::MissingTemplate(self.to_type().to_string())),
         };
 
         match self {
-            TemplateElement::Header(level, elements) => {
+            TemplateElement::Header {
+                level,
+                id,
+                anchor,
+                content,
+                attributes,
+            } => {
+                set_attributes(&mut template, attributes);
                 template.set("level", &format!("{}", level));
+                template.set("id", &escape_html(id));
+                template.set(
+                    "content",
+                    &render_element_list(content, templates, "", false)?,
+                );
+
+                if *anchor {
+                    let anchor = TemplateElement::HeaderAnchor { id: id.clone() };
+                    template.set("anchor", &anchor.render(templates)?);
+                } else {
+                    template.set("anchor", "");
+                }
+            }
+            TemplateElement::HeaderAnchor { id } => {
+                template.set("id", &escape_html(id));
+            }
+            TemplateElement::Paragraph(elements, attributes)
+            | TemplateElement::TightParagraph(elements, attributes) => {
+                set_attributes(&mut template, attributes);
+
+                // A line ending in a hard break is followed by the break instead of a soft one
+                let soft_break = TemplateElement::SoftBreak.render(templates)?;
+                let mut content = String::new();
+                for (index, element) in elements.iter().enumerate() {
+                    let hard_break = index > 0
+                        && matches!(
+                            &elements[index - 1],
+                            TemplateElement::Line(line)
+                                if matches!(line.last(), Some(TemplateElement::HardBreak))
+                        );
+                    if index > 0 && !hard_break {
+                        content.push_str(&soft_break);
+                    }
+                    content.push_str(element.render(templates)?.trim());
+                }
+                template.set("content", &content);
+            }
+            TemplateElement::Line(elements)
+            | TemplateElement::ListItem(elements)
+            | TemplateElement::DefinitionList(elements)
+            | TemplateElement::DefinitionTerm(elements)
+            | TemplateElement::DefinitionDescription(elements)
+            | TemplateElement::BlockQuote(elements)
+            | TemplateElement::TableRow(elements)
+            | TemplateElement::Italic(elements)
+            | TemplateElement::Bold(elements)
+            | TemplateElement::Strikethrough(elements)
+            | TemplateElement::Highlight(elements)
+            | TemplateElement::Superscript(elements)
+            | TemplateElement::Subscript(elements) => {
                 template.set(
                     "content",
                     &render_element_list(elements, templates, "", false)?,
                 );
             }
-            TemplateElement::Paragraph(elements) => {
+            TemplateElement::ThematicBreak
+            | TemplateElement::HardBreak
+            | TemplateElement::SoftBreak => {}
+            TemplateElement::Text(text) => {
+                template.set("content", &escape_html(text));
+            }
+            TemplateElement::Html(html) => {
+                template.set("content", html);
+            }
+            TemplateElement::Code(code) => {
+                template.set("content", &escape_html(code));
+            }
+            TemplateElement::Math { mathml, display } => {
+                template.set("content", mathml);
+                template.set("display", if *display { "block" } else { "inline" });
+            }
+            TemplateElement::Emoji { shortcode, emoji } => {
+                template.set("emoji", emoji);
+                template.set("shortcode", shortcode);
+                template.set("name", &shortcode.replace('_', " "));
+            }
+            TemplateElement::Link {
+                url,
+                title,
+                content,
+                obfuscate,
+                attributes,
+            } => {
+                set_attributes(&mut template, attributes);
+                if *obfuscate {
+                    template.set("url", &encode_entities(url));
+                } else {
+                    template.set("url", &escape_html(url));
+                }
+                template.set("title", &escape_html(title));
+                template.set("title_attr", &optional_attribute("title", title));
                 template.set(
                     "content",
-                    &render_element_list(elements, templates, " ", true)?,
+                    &render_element_list(content, templates, "", false)?,
                 );
             }
-            TemplateElement::Line(elements)
-            | TemplateElement::Italic(elements)
-            | TemplateElement::Bold(elements) => {
+            TemplateElement::Image {
+                url,
+                title,
+                alt,
+                attributes,
+            } => {
+                set_attributes(&mut template, attributes);
+                template.set("url", &escape_html(url));
+                template.set("title", &escape_html(title));
+                template.set("title_attr", &optional_attribute("title", title));
+                template.set("alt", &escape_html(alt));
+            }
+            TemplateElement::CodeBlock {
+                language,
+                content,
+                attributes,
+            } => {
+                set_attributes(&mut template, attributes);
+                let class = if language.is_empty() {
+                    String::new()
+                } else {
+                    format!("language-{}", language)
+                };
+
+                template.set("language", &escape_html(language));
+                template.set("language_attr", &optional_attribute("class", &class));
+                template.set("content", &escape_html(content));
+            }
+            TemplateElement::Admonition {
+                kind,
+                title,
+                content,
+            } => {
+                template.set("kind", &escape_html(kind));
+                template.set("title", &render_element_list(title, templates, "", false)?);
+                template.set(
+                    "content",
+                    &render_element_list(content, templates, "", false)?,
+                );
+            }
+            TemplateElement::TaskItem { checked, content } => {
+                template.set("checked", &checked.to_string());
+                template.set("checked_attr", if *checked { " checked" } else { "" });
+                template.set("glyph", if *checked { "\u{2611}" } else { "\u{2610}" });
+                template.set(
+                    "content",
+                    &render_element_list(content, templates, "", false)?,
+                );
+            }
+            TemplateElement::List {
+                ordered,
+                start,
+                items,
+            } => {
+                let start_attr = if *ordered && *start != 1 {
+                    format!(" start=\"{}\"", start)
+                } else {
+                    String::new()
+                };
+
+                template.set("tag", if *ordered { "ol" } else { "ul" });
+                template.set("start", &format!("{}", start));
+                template.set("start_attr", &start_attr);
+                template.set(
+                    "content",
+                    &render_element_list(items, templates, "", false)?,
+                );
+            }
+            TemplateElement::Table { header, rows } => {
+                template.set("header", &header.render(templates)?);
+                template.set("content", &render_element_list(rows, templates, "", false)?);
+            }
+            TemplateElement::TableCell {
+                header,
+                align,
+                content,
+            } => {
+                let align_attr = if align.is_empty() {
+                    String::new()
+                } else {
+                    format!(" style=\"text-align: {}\"", escape_html(align))
+                };
+
+                template.set("tag", if *header { "th" } else { "td" });
+                template.set("align", &escape_html(align));
+                template.set("align_attr", &align_attr);
+                template.set(
+                    "content",
+                    &render_element_list(content, templates, "", false)?,
+                );
+            }
+            TemplateElement::FootnoteReference { number, occurrence }
+            | TemplateElement::FootnoteBackref { number, occurrence } => {
+                template.set("number", &number.to_string());
+                template.set("occurrence", &occurrence.to_string());
+                template.set("id", &footnote_id(*number));
+                template.set("ref_id", &footnote_ref_id(*number, *occurrence));
+            }
+            TemplateElement::Footnotes(elements) | TemplateElement::Toc(elements) => {
                 template.set(
                     "content",
                     &render_element_list(elements, templates, "", false)?,
                 );
             }
-            TemplateElement::Text(text) => {
-                template.set("content", text);
+            TemplateElement::Footnote {
+                number,
+                references,
+                content,
+            } => {
+                let backrefs = (1..=*references)
+                    .map(|occurrence| TemplateElement::FootnoteBackref {
+                        number: *number,
+                        occurrence,
+                    })
+                    .collect();
+
+                template.set("number", &number.to_string());
+                template.set("id", &footnote_id(*number));
+                template.set(
+                    "backrefs",
+                    &render_element_list(&backrefs, templates, "", false)?,
+                );
+                template.set(
+                    "content",
+                    &render_element_list(content, templates, "", false)?,
+                );
+            }
+            TemplateElement::TocEntry {
+                id,
+                content,
+                children,
+            } => {
+                template.set("id", &escape_html(id));
+                template.set(
+                    "content",
+                    &render_element_list(content, templates, "", false)?,
+                );
+
+                if children.is_empty() {
+                    template.set("children", "");
+                } else {
+                    let children = TemplateElement::Toc(children.clone());
+                    template.set("children", &children.render(templates)?);
+                }
             }
         };
 
@@ -126,6 +726,51 @@ impl TemplateElement {
     }
 }
 
+/// Sets `{attrs}` to the pre-rendered attributes, along with `{attr_id}`, `{attr_class}` and an
+/// `{attr_<name>}` variable for each of the other attributes. Any other `{attr_<name>}` in the
+/// template is set to an empty string, since the element doesn't have that attribute.
+fn set_attributes(template: &mut TemplateString, attributes: &Attributes) {
+    template.set("attrs", &attributes.to_html());
+    template.set(
+        "attr_id",
+        &escape_html(attributes.id.as_deref().unwrap_or("")),
+    );
+    template.set("attr_class", &escape_html(&attributes.classes.join(" ")));
+
+    for (name, value) in &attributes.pairs {
+        template.set(&format!("attr_{}", name), &escape_html(value));
+    }
+
+    let absent: Vec<String> = template
+        .sections
+        .iter()
+        .filter_map(|section| match section {
+            TemplateStringSection::Variable(name) if name.starts_with("attr_") => {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    for name in absent {
+        template.set(&name, "");
+    }
+}
+
+/// The id of a footnote. Footnote ids contain a colon, which `slugify` never produces, so they
+/// can't collide with the ids of headers.
+fn footnote_id(number: u32) -> String {
+    format!("fn:{}", number)
+}
+
+/// The id of a reference to a footnote. Only references after the first are suffixed.
+fn footnote_ref_id(number: u32, occurrence: u32) -> String {
+    if occurrence <= 1 {
+        format!("fnref:{}", number)
+    } else {
+        format!("fnref:{}:{}", number, occurrence)
+    }
+}
+
 pub fn render_element_list(
     elements: &Vec<TemplateElement>,
     templates: &TemplateElementTemplates,
@@ -149,6 +794,14 @@ pub fn render_element_list(
 
 impl TemplateElementTemplates {
     pub fn load(&mut self, file_stem: &str, path: &PathBuf) {
+        if let Some(kind) = file_stem.strip_prefix("admonition_") {
+            self.admonitions.insert(
+                kind.to_lowercase(),
+                TemplateString::parse_string(&fs::read_to_string(path).unwrap()),
+            );
+            return;
+        }
+
         TemplateElementType::from_str(file_stem).map(|element_type| {
             self.templates.insert(
                 element_type,
@@ -164,6 +817,13 @@ impl TemplateElementTemplates {
     pub fn get(&self, element_type: TemplateElementType) -> Option<&TemplateString> {
         self.templates.get(&element_type)
     }
+
+    /// Gets the template for a kind of admonition, falling back to the generic one
+    pub fn get_admonition(&self, kind: &str) -> Option<&TemplateString> {
+        self.admonitions
+            .get(kind)
+            .or_else(|| self.get(TemplateElementType::Admonition))
+    }
 }
 
 impl Display for TemplateElementType {
@@ -173,11 +833,44 @@ impl Display for TemplateElementType {
             "{}",
             match self {
                 TemplateElementType::Header => "Header",
+                TemplateElementType::HeaderAnchor => "HeaderAnchor",
                 TemplateElementType::Paragraph => "Paragraph",
+                TemplateElementType::TightParagraph => "TightParagraph",
+                TemplateElementType::ThematicBreak => "ThematicBreak",
+                TemplateElementType::HardBreak => "HardBreak",
+                TemplateElementType::SoftBreak => "SoftBreak",
                 TemplateElementType::Line => "Line",
                 TemplateElementType::Text => "Text",
+                TemplateElementType::Html => "Html",
+                TemplateElementType::Code => "Code",
+                TemplateElementType::Math => "Math",
+                TemplateElementType::Emoji => "Emoji",
                 TemplateElementType::Italic => "Italic",
                 TemplateElementType::Bold => "Bold",
+                TemplateElementType::Strikethrough => "Strikethrough",
+                TemplateElementType::Highlight => "Highlight",
+                TemplateElementType::Superscript => "Superscript",
+                TemplateElementType::Subscript => "Subscript",
+                TemplateElementType::Link => "Link",
+                TemplateElementType::Image => "Image",
+                TemplateElementType::CodeBlock => "CodeBlock",
+                TemplateElementType::List => "List",
+                TemplateElementType::ListItem => "ListItem",
+                TemplateElementType::TaskItem => "TaskItem",
+                TemplateElementType::DefinitionList => "DefinitionList",
+                TemplateElementType::DefinitionTerm => "DefinitionTerm",
+                TemplateElementType::DefinitionDescription => "DefinitionDescription",
+                TemplateElementType::BlockQuote => "BlockQuote",
+                TemplateElementType::Admonition => "Admonition",
+                TemplateElementType::Table => "Table",
+                TemplateElementType::TableRow => "TableRow",
+                TemplateElementType::TableCell => "TableCell",
+                TemplateElementType::FootnoteReference => "FootnoteReference",
+                TemplateElementType::Footnotes => "Footnotes",
+                TemplateElementType::Footnote => "Footnote",
+                TemplateElementType::FootnoteBackref => "FootnoteBackref",
+                TemplateElementType::Toc => "Toc",
+                TemplateElementType::TocEntry => "TocEntry",
             }
         )
     }
@@ -185,18 +878,49 @@ impl Display for TemplateElementType {
 
 #[cfg(test)]
 mod test {
-    use crate::template::template_element::{TemplateElement, TemplateElementType};
+    use crate::template::template_element::{
+        TemplateElement, TemplateElementTemplates, TemplateElementType,
+    };
+    use crate::template::template_string::TemplateString;
+    use crate::util::html::Attributes;
 
     #[test]
     fn test_template_element_to_type() {
         assert_eq!(
-            TemplateElement::Header(0, vec![]).to_type(),
+            TemplateElement::Header {
+                level: 0,
+                id: String::new(),
+                anchor: false,
+                content: vec![],
+                attributes: Attributes::default(),
+            }
+            .to_type(),
             TemplateElementType::Header
         );
         assert_eq!(
-            TemplateElement::Paragraph(vec![]).to_type(),
+            TemplateElement::HeaderAnchor { id: String::new() }.to_type(),
+            TemplateElementType::HeaderAnchor
+        );
+        assert_eq!(
+            TemplateElement::Paragraph(vec![], Attributes::default()).to_type(),
             TemplateElementType::Paragraph
         );
+        assert_eq!(
+            TemplateElement::TightParagraph(vec![], Attributes::default()).to_type(),
+            TemplateElementType::TightParagraph
+        );
+        assert_eq!(
+            TemplateElement::ThematicBreak.to_type(),
+            TemplateElementType::ThematicBreak
+        );
+        assert_eq!(
+            TemplateElement::HardBreak.to_type(),
+            TemplateElementType::HardBreak
+        );
+        assert_eq!(
+            TemplateElement::SoftBreak.to_type(),
+            TemplateElementType::SoftBreak
+        );
         assert_eq!(
             TemplateElement::Line(vec![]).to_type(),
             TemplateElementType::Line
@@ -205,6 +929,30 @@ mod test {
             TemplateElement::Text(String::new()).to_type(),
             TemplateElementType::Text
         );
+        assert_eq!(
+            TemplateElement::Html(String::new()).to_type(),
+            TemplateElementType::Html
+        );
+        assert_eq!(
+            TemplateElement::Code(String::new()).to_type(),
+            TemplateElementType::Code
+        );
+        assert_eq!(
+            TemplateElement::Math {
+                mathml: String::new(),
+                display: false,
+            }
+            .to_type(),
+            TemplateElementType::Math
+        );
+        assert_eq!(
+            TemplateElement::Emoji {
+                shortcode: String::new(),
+                emoji: String::new(),
+            }
+            .to_type(),
+            TemplateElementType::Emoji
+        );
         assert_eq!(
             TemplateElement::Italic(vec![]).to_type(),
             TemplateElementType::Italic
@@ -213,15 +961,655 @@ mod test {
             TemplateElement::Bold(vec![]).to_type(),
             TemplateElementType::Bold
         );
+        assert_eq!(
+            TemplateElement::Strikethrough(vec![]).to_type(),
+            TemplateElementType::Strikethrough
+        );
+        assert_eq!(
+            TemplateElement::Highlight(vec![]).to_type(),
+            TemplateElementType::Highlight
+        );
+        assert_eq!(
+            TemplateElement::Superscript(vec![]).to_type(),
+            TemplateElementType::Superscript
+        );
+        assert_eq!(
+            TemplateElement::Subscript(vec![]).to_type(),
+            TemplateElementType::Subscript
+        );
+        assert_eq!(
+            TemplateElement::Link {
+                url: String::new(),
+                title: String::new(),
+                content: vec![],
+                obfuscate: false,
+                attributes: Attributes::default(),
+            }
+            .to_type(),
+            TemplateElementType::Link
+        );
+        assert_eq!(
+            TemplateElement::Image {
+                url: String::new(),
+                title: String::new(),
+                alt: String::new(),
+                attributes: Attributes::default(),
+            }
+            .to_type(),
+            TemplateElementType::Image
+        );
+        assert_eq!(
+            TemplateElement::CodeBlock {
+                language: String::new(),
+                content: String::new(),
+                attributes: Attributes::default(),
+            }
+            .to_type(),
+            TemplateElementType::CodeBlock
+        );
+        assert_eq!(
+            TemplateElement::List {
+                ordered: false,
+                start: 1,
+                items: vec![],
+            }
+            .to_type(),
+            TemplateElementType::List
+        );
+        assert_eq!(
+            TemplateElement::ListItem(vec![]).to_type(),
+            TemplateElementType::ListItem
+        );
+        assert_eq!(
+            TemplateElement::TaskItem {
+                checked: false,
+                content: vec![],
+            }
+            .to_type(),
+            TemplateElementType::TaskItem
+        );
+        assert_eq!(
+            TemplateElement::DefinitionList(vec![]).to_type(),
+            TemplateElementType::DefinitionList
+        );
+        assert_eq!(
+            TemplateElement::DefinitionTerm(vec![]).to_type(),
+            TemplateElementType::DefinitionTerm
+        );
+        assert_eq!(
+            TemplateElement::DefinitionDescription(vec![]).to_type(),
+            TemplateElementType::DefinitionDescription
+        );
+        assert_eq!(
+            TemplateElement::BlockQuote(vec![]).to_type(),
+            TemplateElementType::BlockQuote
+        );
+        assert_eq!(
+            TemplateElement::Admonition {
+                kind: "note".to_string(),
+                title: vec![],
+                content: vec![],
+            }
+            .to_type(),
+            TemplateElementType::Admonition
+        );
+        assert_eq!(
+            TemplateElement::Table {
+                header: Box::new(TemplateElement::TableRow(vec![])),
+                rows: vec![],
+            }
+            .to_type(),
+            TemplateElementType::Table
+        );
+        assert_eq!(
+            TemplateElement::TableRow(vec![]).to_type(),
+            TemplateElementType::TableRow
+        );
+        assert_eq!(
+            TemplateElement::TableCell {
+                header: false,
+                align: String::new(),
+                content: vec![],
+            }
+            .to_type(),
+            TemplateElementType::TableCell
+        );
+        assert_eq!(
+            TemplateElement::FootnoteReference {
+                number: 1,
+                occurrence: 1,
+            }
+            .to_type(),
+            TemplateElementType::FootnoteReference
+        );
+        assert_eq!(
+            TemplateElement::Footnotes(vec![]).to_type(),
+            TemplateElementType::Footnotes
+        );
+        assert_eq!(
+            TemplateElement::Footnote {
+                number: 1,
+                references: 1,
+                content: vec![],
+            }
+            .to_type(),
+            TemplateElementType::Footnote
+        );
+        assert_eq!(
+            TemplateElement::FootnoteBackref {
+                number: 1,
+                occurrence: 1,
+            }
+            .to_type(),
+            TemplateElementType::FootnoteBackref
+        );
+        assert_eq!(
+            TemplateElement::Toc(vec![]).to_type(),
+            TemplateElementType::Toc
+        );
+        assert_eq!(
+            TemplateElement::TocEntry {
+                id: String::new(),
+                content: vec![],
+                children: vec![],
+            }
+            .to_type(),
+            TemplateElementType::TocEntry
+        );
     }
 
     #[test]
     fn test_template_element_type_display() {
         assert_eq!(format!("{}", TemplateElementType::Header), "Header");
+        assert_eq!(
+            format!("{}", TemplateElementType::HeaderAnchor),
+            "HeaderAnchor"
+        );
         assert_eq!(format!("{}", TemplateElementType::Paragraph), "Paragraph");
+        assert_eq!(
+            format!("{}", TemplateElementType::TightParagraph),
+            "TightParagraph"
+        );
+        assert_eq!(
+            format!("{}", TemplateElementType::ThematicBreak),
+            "ThematicBreak"
+        );
+        assert_eq!(format!("{}", TemplateElementType::HardBreak), "HardBreak");
+        assert_eq!(format!("{}", TemplateElementType::SoftBreak), "SoftBreak");
         assert_eq!(format!("{}", TemplateElementType::Line), "Line");
         assert_eq!(format!("{}", TemplateElementType::Text), "Text");
+        assert_eq!(format!("{}", TemplateElementType::Html), "Html");
+        assert_eq!(format!("{}", TemplateElementType::Code), "Code");
+        assert_eq!(format!("{}", TemplateElementType::Math), "Math");
+        assert_eq!(format!("{}", TemplateElementType::Emoji), "Emoji");
         assert_eq!(format!("{}", TemplateElementType::Italic), "Italic");
         assert_eq!(format!("{}", TemplateElementType::Bold), "Bold");
+        assert_eq!(
+            format!("{}", TemplateElementType::Strikethrough),
+            "Strikethrough"
+        );
+        assert_eq!(format!("{}", TemplateElementType::Highlight), "Highlight");
+        assert_eq!(
+            format!("{}", TemplateElementType::Superscript),
+            "Superscript"
+        );
+        assert_eq!(format!("{}", TemplateElementType::Subscript), "Subscript");
+        assert_eq!(format!("{}", TemplateElementType::Link), "Link");
+        assert_eq!(format!("{}", TemplateElementType::Image), "Image");
+        assert_eq!(format!("{}", TemplateElementType::CodeBlock), "CodeBlock");
+        assert_eq!(format!("{}", TemplateElementType::List), "List");
+        assert_eq!(format!("{}", TemplateElementType::ListItem), "ListItem");
+        assert_eq!(format!("{}", TemplateElementType::TaskItem), "TaskItem");
+        assert_eq!(
+            format!("{}", TemplateElementType::DefinitionList),
+            "DefinitionList"
+        );
+        assert_eq!(
+            format!("{}", TemplateElementType::DefinitionTerm),
+            "DefinitionTerm"
+        );
+        assert_eq!(
+            format!("{}", TemplateElementType::DefinitionDescription),
+            "DefinitionDescription"
+        );
+        assert_eq!(format!("{}", TemplateElementType::BlockQuote), "BlockQuote");
+        assert_eq!(format!("{}", TemplateElementType::Admonition), "Admonition");
+        assert_eq!(format!("{}", TemplateElementType::Table), "Table");
+        assert_eq!(format!("{}", TemplateElementType::TableRow), "TableRow");
+        assert_eq!(format!("{}", TemplateElementType::TableCell), "TableCell");
+        assert_eq!(
+            format!("{}", TemplateElementType::FootnoteReference),
+            "FootnoteReference"
+        );
+        assert_eq!(format!("{}", TemplateElementType::Footnotes), "Footnotes");
+        assert_eq!(format!("{}", TemplateElementType::Footnote), "Footnote");
+        assert_eq!(
+            format!("{}", TemplateElementType::FootnoteBackref),
+            "FootnoteBackref"
+        );
+        assert_eq!(format!("{}", TemplateElementType::Toc), "Toc");
+        assert_eq!(format!("{}", TemplateElementType::TocEntry), "TocEntry");
+    }
+
+    #[test]
+    fn test_render_link_and_image() {
+        let templates = TemplateElementTemplates::default();
+
+        let link = TemplateElement::Link {
+            url: "https://example.com/?a=1&b=2".to_string(),
+            title: String::new(),
+            content: vec![TemplateElement::Text("Example".to_string())],
+            obfuscate: false,
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            link.render(&templates).unwrap(),
+            "<a href=\"https://example.com/?a=1&amp;b=2\">Example</a>"
+        );
+
+        let email = TemplateElement::Link {
+            url: "mailto:a@b".to_string(),
+            title: String::new(),
+            content: vec![TemplateElement::Html("&#97;".to_string())],
+            obfuscate: true,
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            email.render(&templates).unwrap(),
+            "<a href=\"&#109;&#97;&#105;&#108;&#116;&#111;&#58;&#97;&#64;&#98;\">&#97;</a>"
+        );
+
+        let image = TemplateElement::Image {
+            url: "cat.png".to_string(),
+            title: "A \"cat\"".to_string(),
+            alt: "Cat".to_string(),
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            image.render(&templates).unwrap(),
+            "<img src=\"cat.png\" alt=\"Cat\" title=\"A &quot;cat&quot;\">"
+        );
+    }
+
+    #[test]
+    fn test_render_code_block() {
+        let templates = TemplateElementTemplates::default();
+
+        let code_block = TemplateElement::CodeBlock {
+            language: "html".to_string(),
+            content: "<p>\n  Hello  world\n</p>\n".to_string(),
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            code_block.render(&templates).unwrap(),
+            "<pre><code class=\"language-html\">&lt;p&gt;\n  Hello  world\n&lt;/p&gt;\n</code></pre>"
+        );
+
+        let code_block = TemplateElement::CodeBlock {
+            language: String::new(),
+            content: "x = 1\n".to_string(),
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            code_block.render(&templates).unwrap(),
+            "<pre><code>x = 1\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_render_list() {
+        let templates = TemplateElementTemplates::default();
+        let item = |text: &str| {
+            TemplateElement::ListItem(vec![TemplateElement::TightParagraph(
+                vec![TemplateElement::Line(vec![TemplateElement::Text(
+                    text.to_string(),
+                )])],
+                Attributes::default(),
+            )])
+        };
+
+        let list = TemplateElement::List {
+            ordered: false,
+            start: 1,
+            items: vec![item("one"), item("two")],
+        };
+        assert_eq!(
+            list.render(&templates).unwrap(),
+            "<ul><li>one</li><li>two</li></ul>"
+        );
+
+        let list = TemplateElement::List {
+            ordered: true,
+            start: 1,
+            items: vec![item("one")],
+        };
+        assert_eq!(list.render(&templates).unwrap(), "<ol><li>one</li></ol>");
+
+        let list = TemplateElement::List {
+            ordered: true,
+            start: 7,
+            items: vec![item("seven")],
+        };
+        assert_eq!(
+            list.render(&templates).unwrap(),
+            "<ol start=\"7\"><li>seven</li></ol>"
+        );
+    }
+
+    #[test]
+    fn test_render_definition_list() {
+        let templates = TemplateElementTemplates::default();
+        let list = TemplateElement::DefinitionList(vec![
+            TemplateElement::DefinitionTerm(vec![TemplateElement::Text("Term".to_string())]),
+            TemplateElement::DefinitionDescription(vec![
+                TemplateElement::Paragraph(
+                    vec![TemplateElement::Text("One".to_string())],
+                    Attributes::default(),
+                ),
+                TemplateElement::Paragraph(
+                    vec![TemplateElement::Text("Two".to_string())],
+                    Attributes::default(),
+                ),
+            ]),
+        ]);
+
+        assert_eq!(
+            list.render(&templates).unwrap(),
+            "<dl><dt>Term</dt><dd><p>One</p><p>Two</p></dd></dl>"
+        );
+    }
+
+    #[test]
+    fn test_render_admonition() {
+        let mut templates = TemplateElementTemplates::default();
+        let admonition = |kind: &str| TemplateElement::Admonition {
+            kind: kind.to_string(),
+            title: vec![TemplateElement::Text("Title".to_string())],
+            content: vec![TemplateElement::Text("Body".to_string())],
+        };
+
+        assert_eq!(
+            admonition("note").render(&templates).unwrap(),
+            "<div class=\"admonition admonition-note\"><p class=\"admonition-title\">Title</p>Body</div>"
+        );
+
+        templates.admonitions.insert(
+            "warning".to_string(),
+            TemplateString::parse_string("<aside>{title}: {content}</aside>"),
+        );
+        assert_eq!(
+            admonition("warning").render(&templates).unwrap(),
+            "<aside>Title: Body</aside>"
+        );
+        assert_eq!(
+            admonition("tip").render(&templates).unwrap(),
+            "<div class=\"admonition admonition-tip\"><p class=\"admonition-title\">Title</p>Body</div>"
+        );
+    }
+
+    #[test]
+    fn test_render_task_item() {
+        let mut templates = TemplateElementTemplates::default();
+        let item = |checked: bool| TemplateElement::TaskItem {
+            checked,
+            content: vec![TemplateElement::Text("Task".to_string())],
+        };
+
+        assert_eq!(
+            item(true).render(&templates).unwrap(),
+            "<li class=\"task-list-item\"><input type=\"checkbox\" disabled checked> Task</li>"
+        );
+        assert_eq!(
+            item(false).render(&templates).unwrap(),
+            "<li class=\"task-list-item\"><input type=\"checkbox\" disabled> Task</li>"
+        );
+
+        templates.add(
+            TemplateElementType::TaskItem,
+            TemplateString::parse_string("<li>{glyph} {content}</li>"),
+        );
+        assert_eq!(
+            item(true).render(&templates).unwrap(),
+            "<li>\u{2611} Task</li>"
+        );
+        assert_eq!(
+            item(false).render(&templates).unwrap(),
+            "<li>\u{2610} Task</li>"
+        );
+    }
+
+    #[test]
+    fn test_render_table() {
+        let templates = TemplateElementTemplates::default();
+        let cell = |header: bool, align: &str, text: &str| TemplateElement::TableCell {
+            header,
+            align: align.to_string(),
+            content: vec![TemplateElement::Text(text.to_string())],
+        };
+
+        let table = TemplateElement::Table {
+            header: Box::new(TemplateElement::TableRow(vec![
+                cell(true, "", "Name"),
+                cell(true, "right", "Count"),
+            ])),
+            rows: vec![TemplateElement::TableRow(vec![
+                cell(false, "", "Apples"),
+                cell(false, "right", "3"),
+            ])],
+        };
+        assert_eq!(
+            table.render(&templates).unwrap(),
+            "<table><thead><tr><th>Name</th><th style=\"text-align: right\">Count</th></tr></thead>\
+             <tbody><tr><td>Apples</td><td style=\"text-align: right\">3</td></tr></tbody></table>"
+        );
+    }
+
+    #[test]
+    fn test_render_text_is_escaped() {
+        let templates = TemplateElementTemplates::default();
+
+        let text = TemplateElement::Text("<script>alert(\"&\")</script>".to_string());
+        assert_eq!(
+            text.render(&templates).unwrap(),
+            "&lt;script&gt;alert(&quot;&amp;&quot;)&lt;/script&gt;"
+        );
+
+        let paragraph = TemplateElement::Paragraph(
+            vec![TemplateElement::Line(vec![
+                TemplateElement::Text("1 < 2".to_string()),
+                TemplateElement::Bold(vec![TemplateElement::Text("<b>".to_string())]),
+            ])],
+            Attributes::default(),
+        );
+        assert_eq!(
+            paragraph.render(&templates).unwrap(),
+            "<p>1 &lt; 2<b>&lt;b&gt;</b></p>"
+        );
+    }
+
+    #[test]
+    fn test_render_footnotes() {
+        let templates = TemplateElementTemplates::default();
+
+        let reference = |occurrence: u32| TemplateElement::FootnoteReference {
+            number: 2,
+            occurrence,
+        };
+        assert_eq!(
+            reference(1).render(&templates).unwrap(),
+            "<sup class=\"footnote-ref\"><a href=\"#fn:2\" id=\"fnref:2\">2</a></sup>"
+        );
+        assert_eq!(
+            reference(2).render(&templates).unwrap(),
+            "<sup class=\"footnote-ref\"><a href=\"#fn:2\" id=\"fnref:2:2\">2</a></sup>"
+        );
+
+        let footnotes = TemplateElement::Footnotes(vec![TemplateElement::Footnote {
+            number: 1,
+            references: 2,
+            content: vec![TemplateElement::Text("Note".to_string())],
+        }]);
+        assert_eq!(
+            footnotes.render(&templates).unwrap(),
+            "<section class=\"footnotes\"><ol><li id=\"fn:1\">Note \
+             <a href=\"#fnref:1\" class=\"footnote-backref\">\u{21a9}</a> \
+             <a href=\"#fnref:1:2\" class=\"footnote-backref\">\u{21a9}</a></li></ol></section>"
+        );
+    }
+
+    #[test]
+    fn test_render_line_breaks() {
+        let mut templates = TemplateElementTemplates::default();
+
+        let paragraph = TemplateElement::Paragraph(
+            vec![
+                TemplateElement::Line(vec![
+                    TemplateElement::Text("One".to_string()),
+                    TemplateElement::HardBreak,
+                ]),
+                TemplateElement::Line(vec![TemplateElement::Text("two".to_string())]),
+                TemplateElement::Line(vec![TemplateElement::Text("three".to_string())]),
+            ],
+            Attributes::default(),
+        );
+        assert_eq!(
+            paragraph.render(&templates).unwrap(),
+            "<p>One<br>two three</p>"
+        );
+
+        templates.add(
+            TemplateElementType::SoftBreak,
+            TemplateString::parse_string("\n"),
+        );
+        assert_eq!(
+            paragraph.render(&templates).unwrap(),
+            "<p>One<br>two\nthree</p>"
+        );
+
+        templates.add(
+            TemplateElementType::SoftBreak,
+            TemplateString::parse_string("<br>"),
+        );
+        assert_eq!(
+            paragraph.render(&templates).unwrap(),
+            "<p>One<br>two<br>three</p>"
+        );
+    }
+
+    #[test]
+    fn test_render_header() {
+        let templates = TemplateElementTemplates::default();
+
+        let header = |anchor: bool| TemplateElement::Header {
+            level: 2,
+            id: "getting-started".to_string(),
+            anchor,
+            content: vec![TemplateElement::Text("Getting started".to_string())],
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            header(false).render(&templates).unwrap(),
+            "<h2 id=\"getting-started\">Getting started</h2>"
+        );
+        assert_eq!(
+            header(true).render(&templates).unwrap(),
+            "<h2 id=\"getting-started\">Getting started <a href=\"#getting-started\" class=\"header-anchor\">#</a></h2>"
+        );
+    }
+
+    #[test]
+    fn test_render_toc() {
+        let templates = TemplateElementTemplates::default();
+
+        let entry = |id: &str, children: Vec<TemplateElement>| TemplateElement::TocEntry {
+            id: id.to_string(),
+            content: vec![TemplateElement::Text(id.to_string())],
+            children,
+        };
+        let toc = TemplateElement::Toc(vec![
+            entry("a", vec![entry("a-1", vec![]), entry("a-2", vec![])]),
+            entry("b", vec![]),
+        ]);
+        assert_eq!(
+            toc.render(&templates).unwrap(),
+            "<ul><li><a href=\"#a\">a</a><ul><li><a href=\"#a-1\">a-1</a></li><li><a href=\"#a-2\">a-2</a></li></ul></li><li><a href=\"#b\">b</a></li></ul>"
+        );
+    }
+
+    #[test]
+    fn test_render_attributes() {
+        let mut templates = TemplateElementTemplates::default();
+        let attributes = Attributes {
+            id: Some("intro".to_string()),
+            classes: vec!["lead".to_string()],
+            pairs: vec![("data-level".to_string(), "2".to_string())],
+        };
+
+        let paragraph = TemplateElement::Paragraph(
+            vec![TemplateElement::Line(vec![TemplateElement::Text(
+                "Text".to_string(),
+            )])],
+            attributes.clone(),
+        );
+        assert_eq!(
+            paragraph.render(&templates).unwrap(),
+            "<p id=\"intro\" class=\"lead\" data-level=\"2\">Text</p>"
+        );
+
+        templates.add(
+            TemplateElementType::Paragraph,
+            TemplateString::parse_string(
+                "<p class=\"{attr_class} level-{attr_data-level}\">{content}</p>",
+            ),
+        );
+        assert_eq!(
+            paragraph.render(&templates).unwrap(),
+            "<p class=\"lead level-2\">Text</p>"
+        );
+
+        let image = TemplateElement::Image {
+            url: "cat.png".to_string(),
+            title: String::new(),
+            alt: "A cat".to_string(),
+            attributes,
+        };
+        assert_eq!(
+            image.render(&templates).unwrap(),
+            "<img src=\"cat.png\" alt=\"A cat\" id=\"intro\" class=\"lead\" data-level=\"2\">"
+        );
+    }
+
+    #[test]
+    fn test_render_missing_attributes() {
+        let mut templates = TemplateElementTemplates::default();
+        templates.add(
+            TemplateElementType::Image,
+            TemplateString::parse_string("<img src=\"{url}\" width=\"{attr_width}\">"),
+        );
+
+        let image = TemplateElement::Image {
+            url: "cat.png".to_string(),
+            title: String::new(),
+            alt: String::new(),
+            attributes: Attributes::default(),
+        };
+        assert_eq!(
+            image.render(&templates).unwrap(),
+            "<img src=\"cat.png\" width=\"\">"
+        );
+    }
+
+    #[test]
+    fn test_render_emoji() {
+        let templates = TemplateElementTemplates::default();
+
+        let emoji = TemplateElement::Emoji {
+            shortcode: "thumbs_up".to_string(),
+            emoji: "\u{1f44d}".to_string(),
+        };
+        assert_eq!(
+            emoji.render(&templates).unwrap(),
+            "<span class=\"emoji\" role=\"img\" aria-label=\"thumbs up\">\u{1f44d}</span>"
+        );
     }
 }
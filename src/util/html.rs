@@ -0,0 +1,244 @@
+use entities::ENTITIES;
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Encodes every character as a numeric entity reference, which hides text such as email addresses
+/// from simple scrapers while rendering the same in browsers.
+pub fn encode_entities(text: &str) -> String {
+    text.chars().map(|c| format!("&#{};", c as u32)).collect()
+}
+
+/// Returns true if the HTML contains a `<script>` tag, an event handler attribute such as
+/// `onclick=` or an attribute with a `javascript:` URL
+pub fn contains_script(html: &str) -> bool {
+    lazy_static! {
+        // Browsers also end an attribute name at a `/` or a quote, as in `<img src="x"/onerror=...>`
+        static ref SCRIPT_PATTERN: Regex = Regex::new(
+            r#"(?i)<script(?:[\s/>]|$)|<[^>]*[\s/"']on[a-z]+\s*=|<[^>]*=\s*["']?\s*javascript:"#
+        )
+        .unwrap();
+    }
+
+    SCRIPT_PATTERN.is_match(html).unwrap_or(false)
+}
+
+/// Returns true if the URL runs a script when followed, ignoring case and leading whitespace
+pub fn is_javascript_url(url: &str) -> bool {
+    url.trim_start().to_lowercase().starts_with("javascript:")
+}
+
+/// Attributes given to an element, such as with `{#id .class key=value}` in Markdown
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    /// Any other attributes, in the order they were given
+    pub pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    /// Renders the attributes as HTML, with a space before each one
+    pub fn to_html(&self) -> String {
+        let mut html = optional_attribute("id", self.id.as_deref().unwrap_or(""));
+        html.push_str(&optional_attribute("class", &self.classes.join(" ")));
+
+        for (name, value) in &self.pairs {
+            html.push_str(&format!(" {}=\"{}\"", name, escape_html(value)));
+        }
+
+        html
+    }
+
+    /// Returns the attributes without event handlers such as `onclick` and without any attribute
+    /// whose value is a `javascript:` URL
+    pub fn without_scripts(&self) -> Attributes {
+        let pairs = self
+            .pairs
+            .iter()
+            .filter(|(name, value)| {
+                !name.to_lowercase().starts_with("on") && !is_javascript_url(value)
+            })
+            .cloned()
+            .collect();
+
+        Attributes {
+            pairs,
+            ..self.clone()
+        }
+    }
+}
+
+/// Renders an optional HTML attribute with a leading space, or nothing if the value is empty.
+pub fn optional_attribute(name: &str, value: &str) -> String {
+    if value.is_empty() {
+        String::new()
+    } else {
+        format!(" {}=\"{}\"", name, escape_html(value))
+    }
+}
+
+/// Decodes a named (`&amp;`), decimal (`&#38;`) or hexadecimal (`&#x26;`) entity reference. Invalid
+/// code points decode to the replacement character.
+pub fn decode_entity(entity: &str) -> Option<String> {
+    lazy_static! {
+        static ref NAMED_ENTITIES: HashMap<&'static str, &'static str> = ENTITIES
+            .iter()
+            .filter(|entity| entity.entity.ends_with(';'))
+            .map(|entity| (entity.entity, entity.characters))
+            .collect();
+    }
+
+    let name = entity.strip_prefix('&')?.strip_suffix(';')?;
+
+    let code_point = match name.strip_prefix('#') {
+        Some(number) => match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse::<u32>().ok()?,
+        },
+        None => return NAMED_ENTITIES.get(entity).map(|s| s.to_string()),
+    };
+
+    let c = match code_point {
+        0 => char::REPLACEMENT_CHARACTER,
+        _ => char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER),
+    };
+
+    Some(c.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::html::{
+        contains_script, decode_entity, encode_entities, escape_html, is_javascript_url,
+        optional_attribute, Attributes,
+    };
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html(""), "");
+        assert_eq!(
+            escape_html("No special characters"),
+            "No special characters"
+        );
+        assert_eq!(
+            escape_html("<a href=\"x\">Tom & Jerry</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&lt;/a&gt;"
+        );
+        assert_eq!(escape_html("満クぜひ 🍩"), "満クぜひ 🍩");
+    }
+
+    #[test]
+    fn test_encode_entities() {
+        assert_eq!(encode_entities(""), "");
+        assert_eq!(encode_entities("a@b.c"), "&#97;&#64;&#98;&#46;&#99;");
+        assert_eq!(encode_entities("é"), "&#233;");
+    }
+
+    #[test]
+    fn test_contains_script() {
+        assert!(contains_script("<script>alert(1)</script>"));
+        assert!(contains_script("<SCRIPT src=\"a.js\">"));
+        assert!(contains_script("<img src=\"a.png\" onerror=\"alert(1)\">"));
+        assert!(contains_script("<div\nOnClick = 'x'>"));
+        assert!(!contains_script("<scripts>"));
+        assert!(!contains_script("<p>Click once = twice</p>"));
+        assert!(!contains_script("<a href=\"/online\">"));
+        assert!(!contains_script("<a href=\"/docs/javascript:intro\">"));
+    }
+
+    #[test]
+    fn test_contains_script_bypasses() {
+        assert!(contains_script("<img src=\"x\"/onerror=\"alert(1)\">"));
+        assert!(contains_script("<img src=x/onerror=alert(1)>"));
+        assert!(contains_script("<img src=\"x\"onerror=\"alert(1)\">"));
+        assert!(contains_script("<a href=\"javascript:alert(1)\">"));
+        assert!(contains_script("<a href = ' JavaScript:alert(1)'>"));
+        assert!(contains_script("<iframe src=javascript:alert(1)>"));
+    }
+
+    #[test]
+    fn test_is_javascript_url() {
+        assert!(is_javascript_url("javascript:alert(1)"));
+        assert!(is_javascript_url(" \tJavaScript:alert(1)"));
+        assert!(!is_javascript_url("https://example.com/javascript:"));
+        assert!(!is_javascript_url("/docs/javascript"));
+    }
+
+    #[test]
+    fn test_attributes_to_html() {
+        assert_eq!(Attributes::default().to_html(), "");
+        assert_eq!(
+            Attributes {
+                id: Some("intro".to_string()),
+                classes: vec!["wide".to_string(), "dark".to_string()],
+                pairs: vec![
+                    ("data-x".to_string(), "\"1\"".to_string()),
+                    ("lang".to_string(), String::new()),
+                ],
+            }
+            .to_html(),
+            " id=\"intro\" class=\"wide dark\" data-x=\"&quot;1&quot;\" lang=\"\""
+        );
+    }
+
+    #[test]
+    fn test_attributes_without_scripts() {
+        let attributes = Attributes {
+            id: Some("intro".to_string()),
+            classes: vec![],
+            pairs: vec![
+                ("onclick".to_string(), "alert(1)".to_string()),
+                ("OnMouseOver".to_string(), "alert(1)".to_string()),
+                ("data-href".to_string(), " JavaScript:alert(1)".to_string()),
+                ("lang".to_string(), "en".to_string()),
+            ],
+        };
+        assert_eq!(
+            attributes.without_scripts().to_html(),
+            " id=\"intro\" lang=\"en\""
+        );
+    }
+
+    #[test]
+    fn test_optional_attribute() {
+        assert_eq!(optional_attribute("title", ""), "");
+        assert_eq!(optional_attribute("title", "A title"), " title=\"A title\"");
+        assert_eq!(
+            optional_attribute("title", "\"quoted\""),
+            " title=\"&quot;quoted&quot;\""
+        );
+    }
+
+    #[test]
+    fn test_decode_entity() {
+        assert_eq!(decode_entity("&amp;"), Some("&".to_string()));
+        assert_eq!(decode_entity("&copy;"), Some("©".to_string()));
+        assert_eq!(decode_entity("&ngE;"), Some("≧̸".to_string()));
+        assert_eq!(decode_entity("&#35;"), Some("#".to_string()));
+        assert_eq!(decode_entity("&#x1F369;"), Some("🍩".to_string()));
+        assert_eq!(decode_entity("&#X22;"), Some("\"".to_string()));
+        assert_eq!(decode_entity("&#0;"), Some("\u{FFFD}".to_string()));
+        assert_eq!(decode_entity("&#xD800;"), Some("\u{FFFD}".to_string()));
+
+        assert_eq!(decode_entity("&amp"), None);
+        assert_eq!(decode_entity("&notanentity;"), None);
+        assert_eq!(decode_entity("&#xZZ;"), None);
+    }
+}
@@ -0,0 +1,914 @@
+//! Converts TeX math into MathML, so that equations are shown by the browser without any
+//! JavaScript. Only the commonly used subset of TeX is supported.
+
+use crate::util::html::escape_html;
+use std::fmt;
+use std::mem;
+
+/// Converts TeX math into a MathML `<math>` element, returning it along with a warning for each
+/// construct that isn't supported. Unsupported commands are shown as errors in the output.
+pub fn tex_to_mathml(tex: &str, display: bool) -> (String, Vec<String>) {
+    let mut parser = MathParser {
+        tokens: tokenize(tex),
+        position: 0,
+        display,
+        warnings: vec![],
+    };
+
+    let mut nodes = vec![];
+    loop {
+        nodes.extend(parser.parse_row());
+
+        match parser.next() {
+            Some(token) => parser.warn(format!("unexpected `{}`", token)),
+            None => break,
+        }
+    }
+
+    let math = format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"{}><semantics>{}<annotation encoding=\"application/x-tex\">{}</annotation></semantics></math>",
+        if display { " display=\"block\"" } else { "" },
+        row(nodes),
+        escape_html(tex.trim())
+    );
+
+    (math, parser.warnings)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A command without its backslash, such as `frac`, or a single character for `\,` or `\\`
+    Command(String),
+    Number(String),
+    Letter(char),
+    Symbol(char),
+    Space,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Command(name) => write!(f, "\\{}", name),
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Letter(c) | Token::Symbol(c) => write!(f, "{}", c),
+            Token::Space => write!(f, " "),
+        }
+    }
+}
+
+fn tokenize(tex: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = tex.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\\' => match chars.next() {
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let mut name = c.to_string();
+                    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                        name.push(c);
+                        chars.next();
+                    }
+                    Token::Command(name)
+                }
+                Some(c) if c.is_whitespace() => Token::Command(" ".to_string()),
+                Some(c) => Token::Command(c.to_string()),
+                None => Token::Symbol('\\'),
+            },
+            c if c.is_ascii_digit() => {
+                let mut number = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    // A decimal point is only part of the number if a digit follows it
+                    let decimal_point = c == '.'
+                        && !number.contains('.')
+                        && chars.clone().nth(1).is_some_and(|c| c.is_ascii_digit());
+                    if !c.is_ascii_digit() && !decimal_point {
+                        break;
+                    }
+                    number.push(c);
+                    chars.next();
+                }
+                Token::Number(number)
+            }
+            c if c.is_whitespace() => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                Token::Space
+            }
+            c if c.is_alphabetic() => Token::Letter(c),
+            c => Token::Symbol(c),
+        };
+
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+struct MathParser {
+    tokens: Vec<Token>,
+    position: usize,
+    display: bool,
+    warnings: Vec<String>,
+}
+
+impl MathParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(&Token::Space) {
+            self.position += 1;
+        }
+    }
+
+    fn warn(&mut self, message: String) {
+        if !self.warnings.contains(&message) {
+            self.warnings.push(message);
+        }
+    }
+
+    /// Parses nodes up to the end of the math or of the enclosing group, stopping before the `}`,
+    /// `&`, `\\`, `\right` or `\end` that ends it
+    fn parse_row(&mut self) -> Vec<String> {
+        let mut nodes = vec![];
+
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                None | Some(Token::Symbol('}' | '&')) => break,
+                Some(Token::Command(name)) if name == "\\" || name == "right" || name == "end" => {
+                    break
+                }
+                _ => nodes.push(self.parse_scripted()),
+            }
+        }
+
+        nodes
+    }
+
+    /// Parses an atom along with its subscript and superscript, if it has them
+    fn parse_scripted(&mut self) -> String {
+        let (base, limits) = self.parse_atom();
+
+        let mut subscript = None;
+        let mut superscript = None;
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some(Token::Symbol('_')) if subscript.is_none() => {
+                    self.next();
+                    subscript = Some(self.parse_argument());
+                }
+                Some(Token::Symbol('^')) if superscript.is_none() => {
+                    self.next();
+                    superscript = Some(self.parse_argument());
+                }
+                _ => break,
+            }
+        }
+
+        // Limits of operators such as sums are placed above and below them in display math
+        let (sub_tag, sup_tag, subsup_tag) = if limits && self.display {
+            ("munder", "mover", "munderover")
+        } else {
+            ("msub", "msup", "msubsup")
+        };
+
+        match (subscript, superscript) {
+            (None, None) => base,
+            (Some(sub), None) => format!("<{0}>{1}{2}</{0}>", sub_tag, base, sub),
+            (None, Some(sup)) => format!("<{0}>{1}{2}</{0}>", sup_tag, base, sup),
+            (Some(sub), Some(sup)) => format!("<{0}>{1}{2}{3}</{0}>", subsup_tag, base, sub, sup),
+        }
+    }
+
+    /// Parses the argument of a command or script, which is either a group or a single atom
+    fn parse_argument(&mut self) -> String {
+        self.skip_spaces();
+        match self.peek() {
+            None | Some(Token::Symbol('}' | '&')) => {
+                self.warn("missing argument".to_string());
+                "<mrow></mrow>".to_string()
+            }
+            _ => self.parse_atom().0,
+        }
+    }
+
+    /// Parses a group after its opening `{`
+    fn parse_group(&mut self) -> String {
+        let nodes = self.parse_row();
+        if self.peek() == Some(&Token::Symbol('}')) {
+            self.next();
+        } else {
+            self.warn("missing closing brace `}`".to_string());
+        }
+
+        row(nodes)
+    }
+
+    /// Returns the raw text of a group, for commands such as `\text` whose argument isn't math
+    fn parse_text_argument(&mut self) -> String {
+        self.skip_spaces();
+        if self.peek() != Some(&Token::Symbol('{')) {
+            return self
+                .next()
+                .map(|token| token.to_string())
+                .unwrap_or_default();
+        }
+
+        self.next();
+        let mut text = String::new();
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token {
+                Token::Symbol('{') => depth += 1,
+                Token::Symbol('}') if depth == 0 => return text,
+                Token::Symbol('}') => depth -= 1,
+                _ => {}
+            }
+            text.push_str(&token.to_string());
+        }
+
+        self.warn("missing closing brace `}`".to_string());
+        text
+    }
+
+    /// Parses a single node, returning it along with whether its scripts are limits
+    fn parse_atom(&mut self) -> (String, bool) {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return (String::new(), false),
+        };
+
+        let node = match token {
+            Token::Number(number) => format!("<mn>{}</mn>", number),
+            Token::Letter(c) => format!("<mi>{}</mi>", c),
+            Token::Symbol('{') => self.parse_group(),
+            Token::Symbol('\'') => "<mo>\u{2032}</mo>".to_string(),
+            Token::Symbol('-') => "<mo>\u{2212}</mo>".to_string(),
+            Token::Symbol('*') => "<mo>\u{2217}</mo>".to_string(),
+            Token::Symbol('~') => "<mspace width=\"0.3333em\"/>".to_string(),
+            Token::Symbol(c @ ('^' | '_')) => {
+                self.warn(format!("unexpected `{}`", c));
+                format!("<merror><mtext>{}</mtext></merror>", c)
+            }
+            Token::Symbol(c) => format!("<mo>{}</mo>", escape_html(&c.to_string())),
+            Token::Space => String::new(),
+            Token::Command(name) => return self.parse_command(&name),
+        };
+
+        (node, false)
+    }
+
+    fn parse_command(&mut self, name: &str) -> (String, bool) {
+        if let Some(letter) = greek_letter(name) {
+            let variant = if letter.is_uppercase() {
+                " mathvariant=\"normal\""
+            } else {
+                ""
+            };
+            return (format!("<mi{}>{}</mi>", variant, letter), false);
+        }
+
+        if let Some(symbol) = identifier_symbol(name) {
+            return (format!("<mi>{}</mi>", symbol), false);
+        }
+
+        if let Some(symbol) = operator_symbol(name) {
+            return (format!("<mo>{}</mo>", escape_html(symbol)), false);
+        }
+
+        if let Some((symbol, limits)) = large_operator(name) {
+            return (format!("<mo largeop=\"true\">{}</mo>", symbol), limits);
+        }
+
+        if FUNCTIONS.contains(&name) {
+            return (format!("<mi>{}</mi>", name), false);
+        }
+
+        if LIMIT_FUNCTIONS.contains(&name) {
+            return (format!("<mi>{}</mi>", name), true);
+        }
+
+        if let Some(width) = space_width(name) {
+            return (format!("<mspace width=\"{}\"/>", width), false);
+        }
+
+        if let Some(accent) = accent(name) {
+            let base = self.parse_argument();
+            return (
+                format!(
+                    "<mover accent=\"true\">{}<mo stretchy=\"true\">{}</mo></mover>",
+                    base, accent
+                ),
+                false,
+            );
+        }
+
+        let node = match name {
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.parse_argument();
+                let denominator = self.parse_argument();
+                format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+            }
+            "binom" => {
+                let top = self.parse_argument();
+                let bottom = self.parse_argument();
+                format!(
+                    "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                    top, bottom
+                )
+            }
+            "sqrt" => {
+                self.skip_spaces();
+                let index = if self.peek() == Some(&Token::Symbol('[')) {
+                    self.next();
+                    Some(self.parse_optional_argument())
+                } else {
+                    None
+                };
+
+                let base = self.parse_argument();
+                match index {
+                    Some(index) => format!("<mroot>{}{}</mroot>", base, index),
+                    None => format!("<msqrt>{}</msqrt>", base),
+                }
+            }
+            "text" | "textrm" | "textup" | "textit" | "textbf" | "mbox" => {
+                let text = self.parse_text_argument();
+                format!("<mtext>{}</mtext>", escape_html(&text))
+            }
+            "operatorname" => {
+                let text = self.parse_text_argument();
+                format!("<mi>{}</mi>", escape_html(&text))
+            }
+            "mathrm" | "mathbf" | "mathit" | "mathbb" | "mathcal" | "mathfrak" | "mathsf"
+            | "mathtt" | "boldsymbol" => self.parse_math_variant(name),
+            "overbrace" | "underbrace" => {
+                let base = self.parse_argument();
+                let (tag, brace) = if name == "overbrace" {
+                    ("mover", '\u{23de}')
+                } else {
+                    ("munder", '\u{23df}')
+                };
+                return (
+                    format!(
+                        "<{0}>{1}<mo stretchy=\"true\">{2}</mo></{0}>",
+                        tag, base, brace
+                    ),
+                    true,
+                );
+            }
+            "underline" => {
+                let base = self.parse_argument();
+                format!(
+                    "<munder accentunder=\"true\">{}<mo stretchy=\"true\">_</mo></munder>",
+                    base
+                )
+            }
+            "left" => self.parse_fenced(),
+            "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" | "biggl"
+            | "biggr" | "Biggl" | "Biggr" => {
+                let delimiter = self.parse_delimiter();
+                format!("<mo>{}</mo>", escape_html(&delimiter))
+            }
+            "begin" => self.parse_environment(),
+            "displaystyle" | "textstyle" | "limits" | "nolimits" => String::new(),
+            "{" | "}" | "$" | "%" | "&" | "#" | "_" => {
+                format!("<mo>{}</mo>", escape_html(name))
+            }
+            "|" => "<mo>\u{2016}</mo>".to_string(),
+            _ => {
+                self.warn(format!("unsupported command `\\{}`", name));
+                format!("<merror><mtext>\\{}</mtext></merror>", escape_html(name))
+            }
+        };
+
+        (node, false)
+    }
+
+    /// Parses the optional argument of a command after its `[`
+    fn parse_optional_argument(&mut self) -> String {
+        let mut nodes = vec![];
+        loop {
+            self.skip_spaces();
+            match self.peek() {
+                Some(Token::Symbol(']')) => {
+                    self.next();
+                    break;
+                }
+                None | Some(Token::Symbol('}' | '&')) => {
+                    self.warn("missing closing bracket `]`".to_string());
+                    break;
+                }
+                _ => nodes.push(self.parse_scripted()),
+            }
+        }
+
+        row(nodes)
+    }
+
+    /// Parses the argument of a font command such as `\mathbb`
+    fn parse_math_variant(&mut self, name: &str) -> String {
+        let variant = match name {
+            "mathrm" => "normal",
+            "mathbf" => "bold",
+            "mathit" => "italic",
+            "mathbb" => "double-struck",
+            "mathcal" => "script",
+            "mathfrak" => "fraktur",
+            "mathsf" => "sans-serif",
+            "mathtt" => "monospace",
+            _ => "bold-italic",
+        };
+
+        let start = self.position;
+        let text = self.parse_text_argument();
+
+        // Plain letters can be styled directly, while anything else is parsed as math
+        if !text.is_empty() && text.chars().all(|c| c.is_alphanumeric()) {
+            if variant == "double-struck" && text.chars().count() == 1 {
+                if let Some(letter) = double_struck_letter(text.chars().next().unwrap()) {
+                    return format!("<mi>{}</mi>", letter);
+                }
+            }
+
+            return format!("<mi mathvariant=\"{}\">{}</mi>", variant, text);
+        }
+
+        self.position = start;
+        format!(
+            "<mstyle mathvariant=\"{}\">{}</mstyle>",
+            variant,
+            self.parse_argument()
+        )
+    }
+
+    /// Parses the delimiter after `\left`, `\right` or `\big`, which is empty for `.`
+    fn parse_delimiter(&mut self) -> String {
+        self.skip_spaces();
+        let delimiter = match self.next() {
+            Some(Token::Symbol('.')) => Some(""),
+            Some(Token::Symbol(c @ ('(' | ')' | '[' | ']' | '|' | '/' | '<' | '>'))) => {
+                return match c {
+                    '<' => "\u{27e8}".to_string(),
+                    '>' => "\u{27e9}".to_string(),
+                    c => c.to_string(),
+                };
+            }
+            Some(Token::Command(name)) => match name.as_str() {
+                "{" => Some("{"),
+                "}" => Some("}"),
+                "|" | "Vert" | "lVert" | "rVert" => Some("\u{2016}"),
+                "vert" | "lvert" | "rvert" => Some("|"),
+                "langle" => Some("\u{27e8}"),
+                "rangle" => Some("\u{27e9}"),
+                "lfloor" => Some("\u{230a}"),
+                "rfloor" => Some("\u{230b}"),
+                "lceil" => Some("\u{2308}"),
+                "rceil" => Some("\u{2309}"),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        delimiter.map(str::to_string).unwrap_or_else(|| {
+            self.warn("missing or unsupported delimiter".to_string());
+            String::new()
+        })
+    }
+
+    /// Parses the content of `\left ... \right` after the `\left`
+    fn parse_fenced(&mut self) -> String {
+        let open = self.parse_delimiter();
+        let nodes = self.parse_row();
+
+        let close = if self.peek() == Some(&Token::Command("right".to_string())) {
+            self.next();
+            self.parse_delimiter()
+        } else {
+            self.warn("`\\left` without a matching `\\right`".to_string());
+            String::new()
+        };
+
+        let fence = |delimiter: &str| {
+            if delimiter.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "<mo fence=\"true\" stretchy=\"true\">{}</mo>",
+                    escape_html(delimiter)
+                )
+            }
+        };
+
+        format!(
+            "<mrow>{}{}{}</mrow>",
+            fence(&open),
+            row(nodes),
+            fence(&close)
+        )
+    }
+
+    /// Parses an environment such as `pmatrix` after its `\begin`, as a table
+    fn parse_environment(&mut self) -> String {
+        let name = self.parse_text_argument();
+
+        let (open, close, column_align) = match name.as_str() {
+            "matrix" | "smallmatrix" => ("", "", None),
+            "pmatrix" => ("(", ")", None),
+            "bmatrix" => ("[", "]", None),
+            "Bmatrix" => ("{", "}", None),
+            "vmatrix" => ("|", "|", None),
+            "Vmatrix" => ("\u{2016}", "\u{2016}", None),
+            "cases" => ("{", "", Some("left left")),
+            "aligned" | "align" | "align*" | "split" => ("", "", Some("right left")),
+            "gathered" | "gather" | "gather*" => ("", "", None),
+            "array" => {
+                // The column specification isn't needed
+                self.parse_text_argument();
+                ("", "", None)
+            }
+            _ => {
+                self.warn(format!("unsupported environment `{}`", name));
+                ("", "", None)
+            }
+        };
+
+        let mut rows = vec![];
+        let mut cells = vec![];
+        loop {
+            cells.push(row(self.parse_row()));
+
+            match self.next() {
+                Some(Token::Symbol('&')) => {}
+                Some(Token::Command(command)) if command == "\\" => {
+                    rows.push(mem::take(&mut cells));
+                }
+                Some(Token::Command(command)) if command == "end" => {
+                    let end_name = self.parse_text_argument();
+                    if end_name != name {
+                        self.warn(format!(
+                            "`\\begin{{{}}}` ended by `\\end{{{}}}`",
+                            name, end_name
+                        ));
+                    }
+                    break;
+                }
+                Some(token) => {
+                    self.warn(format!("unexpected `{}`", token));
+                }
+                None => {
+                    self.warn(format!("`\\begin{{{}}}` without a matching `\\end`", name));
+                    break;
+                }
+            }
+        }
+
+        // A `\\` at the end of the last row doesn't start another one
+        if cells.len() > 1 || cells.first().is_some_and(|cell| cell != "<mrow></mrow>") {
+            rows.push(cells);
+        }
+
+        let table = format!(
+            "<mtable{}>{}</mtable>",
+            column_align
+                .map(|align| format!(" columnalign=\"{}\"", align))
+                .unwrap_or_default(),
+            rows.iter()
+                .map(|cells| {
+                    format!(
+                        "<mtr>{}</mtr>",
+                        cells
+                            .iter()
+                            .map(|cell| format!("<mtd>{}</mtd>", cell))
+                            .collect::<String>()
+                    )
+                })
+                .collect::<String>()
+        );
+
+        if open.is_empty() && close.is_empty() {
+            return table;
+        }
+
+        let fence = |delimiter: &str| {
+            if delimiter.is_empty() {
+                String::new()
+            } else {
+                format!("<mo fence=\"true\">{}</mo>", escape_html(delimiter))
+            }
+        };
+
+        format!("<mrow>{}{}{}</mrow>", fence(open), table, fence(close))
+    }
+}
+
+/// Wraps nodes in an `<mrow>` unless there is exactly one
+fn row(mut nodes: Vec<String>) -> String {
+    nodes.retain(|node| !node.is_empty());
+
+    if nodes.len() == 1 {
+        nodes.remove(0)
+    } else {
+        format!("<mrow>{}</mrow>", nodes.concat())
+    }
+}
+
+/// Functions whose names are written upright
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "coth", "log", "ln", "lg", "exp", "det", "dim", "ker", "deg", "gcd", "hom", "arg",
+];
+
+/// Functions whose scripts are placed below them in display math, like `\lim_{x \to 0}`
+const LIMIT_FUNCTIONS: &[&str] = &["lim", "liminf", "limsup", "max", "min", "sup", "inf", "Pr"];
+
+fn greek_letter(name: &str) -> Option<char> {
+    Some(match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "omicron" => 'ο',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    })
+}
+
+/// Symbols that are identifiers rather than operators
+fn identifier_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "emptyset" | "varnothing" => "∅",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "aleph" => "ℵ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "wp" => "℘",
+        _ => return None,
+    })
+}
+
+fn operator_symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "times" => "×",
+        "cdot" => "⋅",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "∙",
+        "oplus" => "⊕",
+        "ominus" => "⊖",
+        "otimes" => "⊗",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "neg" | "lnot" => "¬",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "prec" => "≺",
+        "succ" => "≻",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "supset" => "⊃",
+        "subseteq" => "⊆",
+        "supseteq" => "⊇",
+        "to" | "rightarrow" => "→",
+        "gets" | "leftarrow" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" => "⇔",
+        "longrightarrow" => "⟶",
+        "longleftarrow" => "⟵",
+        "implies" => "⟹",
+        "iff" => "⟺",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "mid" => "∣",
+        "parallel" => "∥",
+        "perp" => "⊥",
+        "angle" => "∠",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "vert" | "lvert" | "rvert" => "|",
+        "Vert" | "lVert" | "rVert" => "‖",
+        "colon" => ":",
+        _ => return None,
+    })
+}
+
+/// Operators such as sums and integrals, along with whether their scripts are limits
+fn large_operator(name: &str) -> Option<(&'static str, bool)> {
+    Some(match name {
+        "sum" => ("∑", true),
+        "prod" => ("∏", true),
+        "coprod" => ("∐", true),
+        "bigcup" => ("⋃", true),
+        "bigcap" => ("⋂", true),
+        "bigoplus" => ("⨁", true),
+        "bigotimes" => ("⨂", true),
+        "int" => ("∫", false),
+        "iint" => ("∬", false),
+        "iiint" => ("∭", false),
+        "oint" => ("∮", false),
+        _ => return None,
+    })
+}
+
+fn space_width(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "," => "0.1667em",
+        ":" | ">" => "0.2222em",
+        ";" => "0.2778em",
+        " " => "0.25em",
+        "!" => "-0.1667em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        _ => return None,
+    })
+}
+
+fn accent(name: &str) -> Option<char> {
+    Some(match name {
+        "hat" | "widehat" => '^',
+        "bar" | "overline" => '\u{203e}',
+        "vec" | "overrightarrow" => '\u{2192}',
+        "tilde" | "widetilde" => '~',
+        "dot" => '\u{02d9}',
+        "ddot" => '\u{00a8}',
+        _ => return None,
+    })
+}
+
+fn double_struck_letter(letter: char) -> Option<char> {
+    Some(match letter {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::mathml::tex_to_mathml;
+
+    /// Converts inline math, leaving out the `<math>` element and annotation around it
+    fn convert(tex: &str) -> (String, Vec<String>) {
+        let (math, warnings) = tex_to_mathml(tex, false);
+        let start = math.find("<semantics>").unwrap() + "<semantics>".len();
+        let end = math.find("<annotation").unwrap();
+
+        (math[start..end].to_string(), warnings)
+    }
+
+    #[test]
+    fn test_tex_to_mathml() {
+        assert_eq!(
+            tex_to_mathml("x^2", true).0,
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\"><semantics><msup><mi>x</mi><mn>2</mn></msup><annotation encoding=\"application/x-tex\">x^2</annotation></semantics></math>"
+        );
+        assert_eq!(
+            convert("a_i + 3.14 - b").0,
+            "<mrow><msub><mi>a</mi><mi>i</mi></msub><mo>+</mo><mn>3.14</mn><mo>\u{2212}</mo><mi>b</mi></mrow>"
+        );
+        assert_eq!(
+            convert("\\frac{1}{\\sqrt[3]{x}}").0,
+            "<mfrac><mn>1</mn><mroot><mi>x</mi><mn>3</mn></mroot></mfrac>"
+        );
+        assert_eq!(
+            convert("\\alpha \\leq \\Gamma").0,
+            "<mrow><mi>α</mi><mo>≤</mo><mi mathvariant=\"normal\">Γ</mi></mrow>"
+        );
+        assert_eq!(
+            convert("\\text{if } x < 0").0,
+            "<mrow><mtext>if </mtext><mi>x</mi><mo>&lt;</mo><mn>0</mn></mrow>"
+        );
+        assert_eq!(
+            convert("\\left( x \\right.").0,
+            "<mrow><mo fence=\"true\" stretchy=\"true\">(</mo><mi>x</mi></mrow>"
+        );
+        assert_eq!(convert("\\mathbb{R}").0, "<mi>ℝ</mi>");
+        assert_eq!(
+            convert("\\begin{pmatrix} a & b \\\\ c & d \\end{pmatrix}").0,
+            "<mrow><mo fence=\"true\">(</mo><mtable><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd></mtr><mtr><mtd><mi>c</mi></mtd><mtd><mi>d</mi></mtd></mtr></mtable><mo fence=\"true\">)</mo></mrow>"
+        );
+    }
+
+    #[test]
+    fn test_tex_to_mathml_limits() {
+        let sum = "\\sum_{i=1}^n i";
+
+        assert!(tex_to_mathml(sum, true)
+            .0
+            .contains("<munderover><mo largeop=\"true\">∑</mo>"));
+        assert!(tex_to_mathml(sum, false)
+            .0
+            .contains("<msubsup><mo largeop=\"true\">∑</mo>"));
+        assert!(tex_to_mathml("\\int_0^1", true).0.contains("<msubsup>"));
+    }
+
+    #[test]
+    fn test_tex_to_mathml_warnings() {
+        assert_eq!(convert("x + y").1, Vec::<String>::new());
+        assert_eq!(
+            convert("\\foo{x}").0,
+            "<mrow><merror><mtext>\\foo</mtext></merror><mi>x</mi></mrow>"
+        );
+        assert_eq!(
+            convert("\\foo + \\foo").1,
+            vec!["unsupported command `\\foo`"]
+        );
+        assert_eq!(convert("\\frac{1}{2").1, vec!["missing closing brace `}`"]);
+        assert_eq!(convert("x}").1, vec!["unexpected `}`"]);
+        assert_eq!(
+            convert("\\left( x").1,
+            vec!["`\\left` without a matching `\\right`"]
+        );
+        assert_eq!(
+            convert("\\begin{foo} x \\end{foo}").1,
+            vec!["unsupported environment `foo`"]
+        );
+    }
+}
@@ -1 +1,3 @@
+pub mod html;
+pub mod mathml;
 pub mod regex_split;